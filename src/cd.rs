@@ -0,0 +1,52 @@
+use std::path::Path;
+
+/// Environment variable the shell wrapper sets to a file it reads after `wt`
+/// exits: a directory written there becomes the shell's new working directory.
+pub const CD_FILE_VAR: &str = "WT_CD_FILE";
+
+/// True when running under a shell wrapper that speaks the `WT_CD_FILE`
+/// protocol, so stdout is free for normal output.
+pub fn handoff_active() -> bool {
+    std::env::var_os(CD_FILE_VAR).is_some_and(|v| !v.is_empty())
+}
+
+/// Ask the shell to change into `dir`. Under the wrapper the path goes to
+/// `WT_CD_FILE`; otherwise it is printed as the only stdout line, which is
+/// what scripts and older wrappers read.
+pub fn request(dir: &Path) -> std::io::Result<()> {
+    let cd_file = std::env::var_os(CD_FILE_VAR).filter(|v| !v.is_empty());
+    request_via(cd_file.as_deref().map(Path::new), dir)
+}
+
+fn request_via(cd_file: Option<&Path>, dir: &Path) -> std::io::Result<()> {
+    match cd_file {
+        Some(file) => std::fs::write(file, dir.to_string_lossy().as_bytes()),
+        None => {
+            println!("{}", dir.display());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_request_writes_cd_file_without_newline() {
+        let tmp = TempDir::new().unwrap();
+        let cd_file = tmp.path().join("cd");
+        request_via(Some(&cd_file), Path::new("/hub/feat")).unwrap();
+        assert_eq!(std::fs::read_to_string(&cd_file).unwrap(), "/hub/feat");
+    }
+
+    #[test]
+    fn test_request_overwrites_earlier_request() {
+        let tmp = TempDir::new().unwrap();
+        let cd_file = tmp.path().join("cd");
+        request_via(Some(&cd_file), Path::new("/hub/a-much-longer-name")).unwrap();
+        request_via(Some(&cd_file), Path::new("/hub/b")).unwrap();
+        assert_eq!(std::fs::read_to_string(&cd_file).unwrap(), "/hub/b");
+    }
+}
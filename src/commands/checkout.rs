@@ -0,0 +1,105 @@
+use crate::commands::{create, switch};
+use crate::confirm::confirm;
+use crate::git::{
+    detect_repo, get_worktree_list, local_branch_exists, remote_branches, run_git_mut, GitError,
+    Worktree,
+};
+
+/// Find the worktree that has `branch` checked out, using the `branch` field
+/// from `git worktree list --porcelain` (`refs/heads/<branch>`).
+pub fn find_worktree_for_branch<'a>(
+    worktrees: &'a [Worktree],
+    branch: &str,
+) -> Option<&'a Worktree> {
+    let full_ref = format!("refs/heads/{}", branch);
+    worktrees
+        .iter()
+        .find(|wt| wt.branch.as_deref() == Some(full_ref.as_str()))
+}
+
+/// Get onto `branch`, whatever its state:
+/// - checked out in some worktree: switch to that worktree
+/// - a local branch, or a branch on exactly one remote: create a worktree for it
+/// - unknown: offer to create it as a new branch from HEAD
+pub fn run(branch: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let anchor = ctx.anchor_dir().to_path_buf();
+    let worktrees = get_worktree_list(&anchor)?;
+
+    if let Some(wt) = find_worktree_for_branch(&worktrees, branch) {
+        return switch::switch_to(&ctx, &worktrees, wt, false);
+    }
+
+    if local_branch_exists(&anchor, branch) {
+        return create::run(branch, Some(branch), None, true);
+    }
+
+    // A branch on exactly one remote is checked out as a new tracking branch by
+    // `git worktree add <path> <branch>`; more than one is ambiguous for git.
+    let remotes = remote_branches(&anchor, branch)?;
+    match remotes.len() {
+        0 => {}
+        1 => return create::run(branch, Some(branch), None, true),
+        _ => {
+            return Err(Box::new(GitError::new(format!(
+                "Branch '{}' exists on several remotes ({}). Create a local branch from one of them first.",
+                branch,
+                remotes.join(", ")
+            ))));
+        }
+    }
+
+    if !confirm(&format!(
+        "Branch '{}' does not exist locally or on a remote. Create it?",
+        branch
+    ))? {
+        return Err(Box::new(GitError::new("Aborted.")));
+    }
+
+    // Create the branch first so the worktree gets exactly this branch name, even
+    // when it is nested (`feature/foo`), then roll it back if the worktree fails.
+    run_git_mut(&anchor, &["branch", branch])?;
+    if let Err(e) = create::run(branch, Some(branch), None, true) {
+        let _ = run_git_mut(&anchor, &["branch", "-D", branch]);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_worktree(path: &str, branch: Option<&str>) -> Worktree {
+        Worktree {
+            path: PathBuf::from(path),
+            head: "abc123".to_string(),
+            branch: branch.map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_worktree_for_branch() {
+        let worktrees = vec![
+            make_worktree("/hub/.bare", None),
+            make_worktree("/hub/main", Some("refs/heads/main")),
+            make_worktree("/hub/work", Some("refs/heads/feature/foo")),
+        ];
+        let found = find_worktree_for_branch(&worktrees, "feature/foo").unwrap();
+        assert_eq!(found.path, PathBuf::from("/hub/work"));
+    }
+
+    #[test]
+    fn test_find_worktree_for_branch_missing_or_detached() {
+        let worktrees = vec![
+            make_worktree("/hub/main", Some("refs/heads/main")),
+            make_worktree("/hub/detached", None),
+        ];
+        assert!(find_worktree_for_branch(&worktrees, "foo").is_none());
+        // Only exact branch names match, not suffixes.
+        assert!(find_worktree_for_branch(&worktrees, "ain").is_none());
+    }
+}
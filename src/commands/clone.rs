@@ -2,7 +2,9 @@ use std::fs;
 use std::path::Path;
 use std::process::{Command, Stdio};
 
-use crate::git::{self, GitError};
+use crate::cd;
+use crate::dry_run;
+use crate::git::{self, GitError, Layout, RepoContext};
 use crate::hooks_template::default_hooks_content;
 
 /// Create .wtree directory with hooks.toml
@@ -46,6 +48,68 @@ fn extract_repo_name(url: &str) -> Result<String, GitError> {
     Ok(name.to_string())
 }
 
+/// Parse the default branch out of `git ls-remote --symref <url> HEAD` output,
+/// whose first line is `ref: refs/heads/<branch>\tHEAD`.
+fn parse_symref_head(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (target, name) = line.strip_prefix("ref: ")?.split_once('\t')?;
+        (name == "HEAD")
+            .then(|| target.strip_prefix("refs/heads/"))
+            .flatten()
+            .map(|b| b.to_string())
+    })
+}
+
+/// `path` relative to the current directory when it is below it, as the clone
+/// messages have always shown it.
+fn display_path(path: &Path) -> String {
+    let cwd = std::env::current_dir().ok();
+    let cwd = cwd.map(|c| c.canonicalize().unwrap_or(c));
+    cwd.and_then(|c| path.strip_prefix(c).ok().map(|p| p.display().to_string()))
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Report what `run` would do without touching the filesystem. The default
+/// branch is looked up with a read-only `git ls-remote`.
+fn report_clone(url: &str, repo_dir: &Path) {
+    let bare_path = repo_dir.join(".bare");
+    let bare = bare_path.to_string_lossy();
+    let dir = repo_dir.to_string_lossy();
+    dry_run::report(&format!("mkdir {}", repo_dir.display()));
+    dry_run::report(&dry_run::format_command(
+        "git",
+        &["clone", "--bare", url, bare.as_ref()],
+    ));
+    dry_run::report(&format!("write {}", repo_dir.join(".git").display()));
+    dry_run::report(&format!(
+        "write {}",
+        repo_dir.join(".wtree").join("hooks.toml").display()
+    ));
+    dry_run::report(&dry_run::format_command(
+        "git",
+        &[
+            "-C",
+            dir.as_ref(),
+            "config",
+            "remote.origin.fetch",
+            "+refs/heads/*:refs/remotes/origin/*",
+        ],
+    ));
+    let default_branch = Command::new("git")
+        .args(["ls-remote", "--symref", url, "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .and_then(|o| parse_symref_head(&String::from_utf8_lossy(&o.stdout)));
+    match default_branch {
+        Some(b) => dry_run::report(&dry_run::format_command(
+            "git",
+            &["-C", dir.as_ref(), "worktree", "add", &b, &b],
+        )),
+        None => eprintln!("Warning: could not determine the remote's default branch"),
+    }
+}
+
 pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
     let repo_name = extract_repo_name(url)?;
     let repo_dir = std::env::current_dir()?.join(&repo_name);
@@ -57,7 +121,15 @@ pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
         ))));
     }
 
-    if !switch {
+    if dry_run::is_enabled() {
+        report_clone(url, &repo_dir);
+        return Ok(());
+    }
+
+    // Without the WT_CD_FILE handoff, `-s` reserves stdout for the path alone
+    let quiet = switch && !cd::handoff_active();
+
+    if !quiet {
         println!("Cloning {} into {}/", url, repo_name);
     }
 
@@ -68,12 +140,12 @@ pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
     let bare_path = repo_dir.join(".bare");
     let status = Command::new("git")
         .args(["clone", "--bare", url, bare_path.to_str().unwrap()])
-        .stdout(if switch {
+        .stdout(if quiet {
             Stdio::null()
         } else {
             Stdio::inherit()
         })
-        .stderr(if switch {
+        .stderr(if quiet {
             Stdio::null()
         } else {
             Stdio::inherit()
@@ -92,7 +164,7 @@ pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
 
     // Create .wtree directory with template hooks.toml
     if let Err(e) = create_wtree_config(&repo_dir) {
-        if !switch {
+        if !quiet {
             eprintln!("Warning: Failed to create .wtree config: {}", e);
         }
     }
@@ -108,22 +180,35 @@ pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
         ])
         .status()?;
 
-    if !config_status.success() && !switch {
+    if !config_status.success() && !quiet {
         eprintln!("Warning: Failed to configure fetch refspec");
     }
 
-    // Detect and create worktree for default branch
+    // Detect and create worktree for default branch, where a global
+    // `worktree_base` puts it (next to `.bare` by default)
+    let mut landing = repo_dir.clone();
     if let Some(default_branch) = get_default_branch(&repo_dir) {
-        // When running from repo_dir, worktree path is just the branch name
+        let ctx = RepoContext {
+            layout: Layout::Bare {
+                hub_root: repo_dir.canonicalize().unwrap_or_else(|_| repo_dir.clone()),
+            },
+        };
+        let worktree_path = ctx.worktree_base().join(&default_branch);
+        let shown = display_path(&worktree_path);
         let worktree_status = Command::new("git")
             .current_dir(&repo_dir)
-            .args(["worktree", "add", &default_branch, &default_branch])
-            .stdout(if switch {
+            .args([
+                "worktree",
+                "add",
+                &worktree_path.to_string_lossy(),
+                &default_branch,
+            ])
+            .stdout(if quiet {
                 Stdio::null()
             } else {
                 Stdio::inherit()
             })
-            .stderr(if switch {
+            .stderr(if quiet {
                 Stdio::null()
             } else {
                 Stdio::inherit()
@@ -131,37 +216,38 @@ pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
             .status()?;
 
         if worktree_status.success() {
-            if switch {
-                // Print only the path for shell wrapper to cd into
-                println!("{}", repo_dir.join(&default_branch).display());
-            } else {
+            landing = worktree_path;
+            if !quiet {
                 println!("Created bare repository at {}/", repo_name);
-                println!(
-                    "Created worktree '{}' at {}/{}/",
-                    default_branch, repo_name, default_branch
-                );
-                println!("Use 'cd {}/{}' to start working", repo_name, default_branch);
+                println!("Created worktree '{}' at {}/", default_branch, shown);
             }
-        } else if switch {
-            // Fallback to repo root if worktree creation failed
-            println!("{}", repo_dir.display());
-        } else {
+            if !switch {
+                println!("Use 'cd {}' to start working", shown);
+            }
+        } else if !quiet {
             println!("Created bare repository at {}/", repo_name);
             eprintln!("Warning: Failed to create default branch worktree");
+            if !switch {
+                println!(
+                    "Use 'cd {}' then 'wt create <name>' to create a worktree",
+                    repo_name
+                );
+            }
+        }
+    } else if !quiet {
+        println!("Created bare repository at {}/", repo_name);
+        if !switch {
             println!(
                 "Use 'cd {}' then 'wt create <name>' to create a worktree",
                 repo_name
             );
         }
-    } else if switch {
-        // No default branch detected, switch to repo root
-        println!("{}", repo_dir.display());
-    } else {
-        println!("Created bare repository at {}/", repo_name);
-        println!(
-            "Use 'cd {}' then 'wt create <name>' to create a worktree",
-            repo_name
-        );
+    }
+
+    // Hand the shell the default-branch worktree, or the repo root when there
+    // is none
+    if switch {
+        cd::request(&landing)?;
     }
 
     Ok(())
@@ -171,6 +257,14 @@ pub fn run(url: &str, switch: bool) -> Result<(), Box<dyn std::error::Error>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_symref_head() {
+        let output = "ref: refs/heads/main\tHEAD\n1234abcd\tHEAD\n";
+        assert_eq!(parse_symref_head(output), Some("main".to_string()));
+        assert_eq!(parse_symref_head("1234abcd\tHEAD\n"), None);
+        assert_eq!(parse_symref_head(""), None);
+    }
+
     #[test]
     fn test_extract_repo_name_https() {
         assert_eq!(
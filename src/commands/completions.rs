@@ -0,0 +1,256 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+use clap_complete::{generate, Shell};
+
+use crate::git::{detect_repo, get_worktree_list, run_git_in_dir};
+
+/// Shells `wt completions` supports, each with runtime worktree/branch values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Values `wt __complete` can list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionKind {
+    Worktrees,
+    Branches,
+}
+
+// Appended to clap's static script: worktree-name positionals (`switch`, `go`,
+// `remove`) and `--base`, and branch values (`checkout`, `--checkout`), are
+// asked from `wt __complete` at completion time; everything else falls through
+// to the generated completer.
+
+const BASH_DYNAMIC: &str = r#"
+_wt_dynamic() {
+  local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}" kind="" sub="" i
+  # The subcommand is the first word that is not an option (`wt --dry-run rm`)
+  for ((i = 1; i < COMP_CWORD; i++)); do
+    if [[ "${COMP_WORDS[i]}" != -* ]]; then
+      sub="${COMP_WORDS[i]}"
+      break
+    fi
+  done
+  case "$prev" in
+    --base) kind=worktrees ;;
+    --checkout) kind=branches ;;
+    *)
+      if [[ -n "$sub" && "$cur" != -* ]]; then
+        case "$sub" in
+          switch|sw|go|remove|rm) kind=worktrees ;;
+          checkout|co) kind=branches ;;
+        esac
+      fi
+      ;;
+  esac
+  if [[ -n "$kind" ]]; then
+    COMPREPLY=($(compgen -W "$(command wt __complete "$kind" 2>/dev/null)" -- "$cur"))
+    return
+  fi
+  _wt "$@"
+}
+complete -F _wt_dynamic -o bashdefault -o default wt
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_wt_dynamic() {
+  local kind="" sub="" i
+  # The subcommand is the first word that is not an option (`wt --dry-run rm`)
+  for ((i = 2; i < CURRENT; i++)); do
+    if [[ "${words[i]}" != -* ]]; then
+      sub="${words[i]}"
+      break
+    fi
+  done
+  case "${words[CURRENT-1]}" in
+    --base) kind=worktrees ;;
+    --checkout) kind=branches ;;
+    *)
+      if [[ -n "$sub" && "${words[CURRENT]}" != -* ]]; then
+        case "$sub" in
+          switch|sw|go|remove|rm) kind=worktrees ;;
+          checkout|co) kind=branches ;;
+        esac
+      fi
+      ;;
+  esac
+  if [[ -n "$kind" ]]; then
+    local -a candidates
+    candidates=(${(f)"$(command wt __complete $kind 2>/dev/null)"})
+    compadd -a candidates
+    return
+  fi
+  _wt "$@"
+}
+compdef _wt_dynamic wt
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c wt -f -n "__fish_seen_subcommand_from switch sw go remove rm" -a "(command wt __complete worktrees 2>/dev/null)"
+complete -c wt -f -n "__fish_seen_subcommand_from checkout co" -a "(command wt __complete branches 2>/dev/null)"
+complete -c wt -f -n "__fish_seen_subcommand_from create c" -l base -r -a "(command wt __complete worktrees 2>/dev/null)"
+complete -c wt -f -n "__fish_seen_subcommand_from create c" -l checkout -r -a "(command wt __complete branches 2>/dev/null)"
+"#;
+
+/// Print the completion script for `shell`: clap's static completions for
+/// `cmd`, followed by the runtime value hooks.
+pub fn run(
+    shell: CompletionShell,
+    mut cmd: clap::Command,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (generator, dynamic) = match shell {
+        CompletionShell::Bash => (Shell::Bash, BASH_DYNAMIC),
+        CompletionShell::Zsh => (Shell::Zsh, ZSH_DYNAMIC),
+        CompletionShell::Fish => (Shell::Fish, FISH_DYNAMIC),
+    };
+    let mut stdout = std::io::stdout().lock();
+    generate(generator, &mut cmd, "wt", &mut stdout);
+    stdout.write_all(dynamic.as_bytes())?;
+    Ok(())
+}
+
+/// Branch names from `git for-each-ref --format=%(refname) refs/heads refs/remotes`:
+/// local branches and remote branches without their remote prefix, deduplicated,
+/// skipping symbolic `HEAD` refs.
+pub fn parse_branch_names(output: &str) -> Vec<String> {
+    let mut names: Vec<String> = output
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            line.strip_prefix("refs/heads/").or_else(|| {
+                line.strip_prefix("refs/remotes/")
+                    .and_then(|rest| rest.split_once('/'))
+                    .map(|(_, branch)| branch)
+            })
+        })
+        .filter(|name| *name != "HEAD")
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Print completion candidates, one per line. Prints nothing (and succeeds)
+/// outside a repository, so completion never shows an error.
+pub fn run_complete(kind: CompletionKind) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(ctx) = detect_repo() else {
+        return Ok(());
+    };
+    let anchor = ctx.anchor_dir();
+
+    let candidates: Vec<String> = match kind {
+        CompletionKind::Worktrees => get_worktree_list(anchor)
+            .unwrap_or_default()
+            .iter()
+            .filter(|wt| wt.head != "(bare)")
+            .filter_map(|wt| wt.path.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect(),
+        CompletionKind::Branches => run_git_in_dir(
+            anchor,
+            &[
+                "for-each-ref",
+                "--format=%(refname)",
+                "refs/heads",
+                "refs/remotes",
+            ],
+        )
+        .map(|out| parse_branch_names(&out))
+        .unwrap_or_default(),
+    };
+
+    for candidate in candidates {
+        println!("{}", candidate);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_branch_names() {
+        let output = "refs/heads/main\nrefs/heads/feature/x\nrefs/remotes/origin/HEAD\nrefs/remotes/origin/main\nrefs/remotes/upstream/fix\n";
+        assert_eq!(parse_branch_names(output), vec!["feature/x", "fix", "main"]);
+    }
+
+    #[test]
+    fn test_parse_branch_names_empty() {
+        assert!(parse_branch_names("").is_empty());
+    }
+
+    /// Runs the bash completer on `words` (the last one being completed) with
+    /// a stub `wt` that lists two worktrees. `None` when bash is not installed.
+    #[cfg(unix)]
+    fn complete_in_bash(words: &[&str]) -> Option<String> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin = tempfile::TempDir::new().unwrap();
+        let stub = bin.path().join("wt");
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\n[ \"$2\" = worktrees ] && printf 'alpha\\nbeta\\n'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let quoted: Vec<String> = words.iter().map(|w| format!("'{}'", w)).collect();
+        let script = format!(
+            "_wt() {{ COMPREPLY=(clap); }}\n{}\nCOMP_WORDS=({})\nCOMP_CWORD={}\n\
+             _wt_dynamic\necho \"${{COMPREPLY[*]}}\"",
+            BASH_DYNAMIC,
+            quoted.join(" "),
+            words.len() - 1
+        );
+        let path = format!(
+            "{}:{}",
+            bin.path().display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let output = std::process::Command::new("bash")
+            .args(["--norc", "-c", &script])
+            .env("PATH", path)
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bash_completion_finds_subcommand_after_global_flags() {
+        let Some(plain) = complete_in_bash(&["wt", "switch", ""]) else {
+            eprintln!("skipping: bash is not installed");
+            return;
+        };
+        assert_eq!(plain, "alpha beta");
+        assert_eq!(
+            complete_in_bash(&["wt", "--dry-run", "rm", "a"]).unwrap(),
+            "alpha"
+        );
+        assert_eq!(
+            complete_in_bash(&["wt", "--dry-run", "create", "x", "--base", ""]).unwrap(),
+            "alpha beta"
+        );
+        // Not a worktree position: falls through to clap's completer
+        assert_eq!(complete_in_bash(&["wt", "--dry-run", ""]).unwrap(), "clap");
+        assert_eq!(complete_in_bash(&["wt", "list", ""]).unwrap(), "clap");
+    }
+
+    #[test]
+    fn test_dynamic_snippets_call_complete_entry_point() {
+        for snippet in [BASH_DYNAMIC, ZSH_DYNAMIC, FISH_DYNAMIC] {
+            assert!(snippet.contains("command wt __complete"));
+            assert!(snippet.contains("worktrees"));
+            assert!(snippet.contains("branches"));
+        }
+        // bash and zsh fall back to clap's generated `_wt` function
+        assert!(BASH_DYNAMIC.contains("_wt \"$@\""));
+        assert!(ZSH_DYNAMIC.contains("_wt \"$@\""));
+    }
+}
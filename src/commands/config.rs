@@ -0,0 +1,510 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use toml_edit::{DocumentMut, Item};
+
+use crate::commands::list::align_columns;
+use crate::config::{
+    check_config, config_layers, global_config_path, load_config_file, parse_config,
+    repo_config_path, WtreeConfig,
+};
+use crate::dry_run;
+use crate::git::{detect_repo, GitError, RepoContext};
+use crate::hooks::{hooks_path, parse_hooks};
+use crate::hooks_template::get_global_default_hooks_path;
+use crate::trash::DEFAULT_TRASH_DAYS;
+use crate::worktree_env::is_valid_key;
+
+/// Type of value a config key takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    String,
+    Bool,
+    Integer,
+}
+
+/// Keys `wt config` can set. `env.<NAME>` sets a variable of the `[env]` table.
+const KEYS: &[(&str, Kind)] = &[
+    ("worktree_base", Kind::String),
+    ("keep_subdir", Kind::Bool),
+    ("list_format", Kind::String),
+    ("trash_days", Kind::Integer),
+];
+
+fn key_kind(key: &str) -> Result<Kind, GitError> {
+    if let Some(var) = key.strip_prefix("env.") {
+        return if is_valid_key(var) {
+            Ok(Kind::String)
+        } else {
+            Err(GitError::new(format!(
+                "Invalid variable name '{}' in '{}'",
+                var, key
+            )))
+        };
+    }
+    KEYS.iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| {
+            let known: Vec<&str> = KEYS.iter().map(|(name, _)| *name).collect();
+            GitError::new(format!(
+                "Unknown config key '{}' (known: {}, env.<NAME>)",
+                key,
+                known.join(", ")
+            ))
+        })
+}
+
+fn parse_value(key: &str, raw: &str) -> Result<toml_edit::Value, GitError> {
+    match key_kind(key)? {
+        Kind::String => Ok(raw.into()),
+        Kind::Bool => raw
+            .parse::<bool>()
+            .map(Into::into)
+            .map_err(|_| GitError::new(format!("'{}' takes true or false, got '{}'", key, raw))),
+        Kind::Integer => raw
+            .parse::<u32>()
+            .map(|n| i64::from(n).into())
+            .map_err(|_| GitError::new(format!("'{}' takes a whole number, got '{}'", key, raw))),
+    }
+}
+
+/// Set `key` in `doc`, keeping the comments and layout around it.
+fn set_in_document(
+    doc: &mut DocumentMut,
+    key: &str,
+    mut value: toml_edit::Value,
+) -> Result<(), GitError> {
+    let (table, name) = match key.strip_prefix("env.") {
+        Some(var) => {
+            let env = doc
+                .entry("env")
+                .or_insert_with(toml_edit::table)
+                .as_table_like_mut()
+                .ok_or_else(|| GitError::new("'env' in the config file is not a table"))?;
+            (env, var)
+        }
+        None => (doc.as_table_mut() as &mut dyn toml_edit::TableLike, key),
+    };
+    // Replace in place so the key's own comments survive, and carry over the
+    // trailing comment of the old value
+    match table.get_mut(name) {
+        Some(item) => {
+            if let Some(old) = item.as_value() {
+                *value.decor_mut() = old.decor().clone();
+            }
+            *item = Item::Value(value);
+        }
+        None => {
+            table.insert(name, Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+/// The settings `cfg` defines, as `(key, value)` with `[env]` variables as
+/// `env.<NAME>`.
+fn entries(cfg: WtreeConfig) -> Vec<(String, toml::Value)> {
+    let fields = [
+        ("worktree_base", cfg.worktree_base.map(toml::Value::String)),
+        ("keep_subdir", cfg.keep_subdir.map(toml::Value::Boolean)),
+        ("list_format", cfg.list_format.map(toml::Value::String)),
+        (
+            "trash_days",
+            cfg.trash_days.map(|n| toml::Value::Integer(n as i64)),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .chain(
+            cfg.env
+                .into_iter()
+                .map(|(var, value)| (format!("env.{}", var), toml::Value::String(value))),
+        )
+        .collect()
+}
+
+/// Every setting defined in `layers`, as `key -> (value, file)`, later files
+/// winning. Files are read like `load_config` reads them, so settings it
+/// ignores (with a warning) are not listed.
+fn collect_settings(layers: &[PathBuf]) -> BTreeMap<String, (toml::Value, PathBuf)> {
+    let mut settings = BTreeMap::new();
+    for path in layers {
+        for (key, value) in entries(load_config_file(path)) {
+            settings.insert(key, (value, path.clone()));
+        }
+    }
+    settings
+}
+
+/// Built-in values of the keys that have one.
+fn defaults(ctx: Option<&RepoContext>) -> Vec<(&'static str, toml::Value)> {
+    let mut values = vec![
+        ("keep_subdir", toml::Value::Boolean(true)),
+        (
+            "trash_days",
+            toml::Value::Integer(DEFAULT_TRASH_DAYS as i64),
+        ),
+    ];
+    if let Some(ctx) = ctx {
+        let base = ctx.worktree_base().display().to_string();
+        values.push(("worktree_base", toml::Value::String(base)));
+    }
+    values
+}
+
+/// Effective settings for the current repo (or only the global files outside
+/// one), with where each came from: a file, or `None` for the built-in default.
+fn effective_settings() -> BTreeMap<String, (toml::Value, Option<PathBuf>)> {
+    let ctx = detect_repo().ok();
+    let mut settings: BTreeMap<String, (toml::Value, Option<PathBuf>)> =
+        collect_settings(&config_layers(ctx.as_ref()))
+            .into_iter()
+            .map(|(key, (value, path))| (key, (value, Some(path))))
+            .collect();
+    for (key, value) in defaults(ctx.as_ref()) {
+        settings.entry(key.to_string()).or_insert((value, None));
+    }
+    settings
+}
+
+/// Print every effective setting and the file it came from.
+pub fn run_list() -> Result<(), Box<dyn std::error::Error>> {
+    let rows: Vec<Vec<String>> = effective_settings()
+        .into_iter()
+        .map(|(key, (value, origin))| {
+            vec![
+                format!("{} = {}", key, value),
+                match origin {
+                    Some(path) => format!("({})", path.display()),
+                    None => "(default)".to_string(),
+                },
+            ]
+        })
+        .collect();
+    for line in align_columns(&rows) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Print the effective value of `key` (strings without quotes).
+pub fn run_get(key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    key_kind(key)?;
+    match effective_settings().remove(key) {
+        Some((toml::Value::String(s), _)) => println!("{}", s),
+        Some((value, _)) => println!("{}", value),
+        None => return Err(Box::new(GitError::new(format!("'{}' is not set", key)))),
+    }
+    Ok(())
+}
+
+/// The file `set`/`edit` work on: the repo's config, or the global one.
+fn target_file(global: bool) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if global {
+        Ok(global_config_path()
+            .ok_or_else(|| GitError::new("Cannot locate the global config: HOME is not set"))?)
+    } else {
+        Ok(repo_config_path(&detect_repo()?))
+    }
+}
+
+/// `content` with `key` set to `raw`, refused when the result would not be a
+/// valid config file (see `wt config validate`).
+fn edit_content(path: &Path, content: &str, key: &str, raw: &str) -> Result<String, GitError> {
+    let value = parse_value(key, raw)?;
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .map_err(|e| GitError::new(format!("Failed to parse {}: {}", path.display(), e)))?;
+    set_in_document(&mut doc, key, value)?;
+
+    let edited = doc.to_string();
+    let problems = file_problems(path, &edited, FileKind::Config);
+    if !problems.is_empty() {
+        return Err(GitError::new(format!(
+            "Not writing {}:\n{}",
+            path.display(),
+            problems.join("\n")
+        )));
+    }
+    Ok(edited)
+}
+
+/// Set `key` to `raw` in the repo's config file, or the global one.
+pub fn run_set(key: &str, raw: &str, global: bool) -> Result<(), Box<dyn std::error::Error>> {
+    key_kind(key)?;
+    let path = target_file(global)?;
+
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    let edited = edit_content(&path, &content, key, raw)?;
+
+    if let Some(dir) = path.parent() {
+        dry_run::create_dir_all(dir)?;
+    }
+    dry_run::write_file(&path, &edited)?;
+    println!(
+        "{} {} = {} in {}",
+        dry_run::label("Set", "Would set"),
+        key,
+        raw,
+        path.display()
+    );
+    Ok(())
+}
+
+fn run_editor(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "vi".to_string());
+    // Through the shell, so EDITOR="code --wait" works
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .map_err(|e| GitError::new(format!("Failed to run editor '{}': {}", editor, e)))?;
+    if !status.success() {
+        return Err(Box::new(GitError::new(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        ))));
+    }
+    Ok(())
+}
+
+/// Open the repo's config file (or the global one) in `$VISUAL`/`$EDITOR`.
+pub fn run_edit(global: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path = target_file(global)?;
+    if dry_run::is_enabled() {
+        dry_run::report(&format!("edit {}", path.display()));
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if !path.exists() {
+        std::fs::write(&path, "")?;
+    }
+    run_editor(&path)?;
+
+    let content = std::fs::read_to_string(&path)?;
+    for problem in file_problems(&path, &content, FileKind::Config) {
+        eprintln!("Warning: {}", problem);
+    }
+    Ok(())
+}
+
+/// What a file checked by `wt config validate` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Config,
+    Hooks,
+}
+
+/// Every problem with one file, each as `path[:line:column]: message`.
+fn file_problems(path: &Path, content: &str, kind: FileKind) -> Vec<String> {
+    match kind {
+        FileKind::Config => match parse_config(path, content) {
+            Ok((cfg, mut problems)) => {
+                problems.extend(
+                    check_config(&cfg)
+                        .into_iter()
+                        .map(|problem| format!("{}: {}", path.display(), problem)),
+                );
+                problems
+            }
+            Err(e) => vec![e],
+        },
+        FileKind::Hooks => parse_hooks(path, content).err().into_iter().collect(),
+    }
+}
+
+/// Check every config and hooks file that applies here (the global ones, and
+/// the repo's when in one). Fails when any has a problem.
+pub fn run_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo().ok();
+    let mut files: Vec<(PathBuf, FileKind)> = config_layers(ctx.as_ref())
+        .into_iter()
+        .map(|path| (path, FileKind::Config))
+        .collect();
+    files.extend(get_global_default_hooks_path().map(|path| (path, FileKind::Hooks)));
+    files.extend(ctx.as_ref().map(|ctx| (hooks_path(ctx), FileKind::Hooks)));
+
+    let mut checked = 0;
+    let mut problems = 0;
+    for (path, kind) in files {
+        let found = match std::fs::read_to_string(&path) {
+            Ok(content) => file_problems(&path, &content, kind),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => vec![format!("{}: {}", path.display(), e)],
+        };
+        checked += 1;
+        if found.is_empty() {
+            println!("ok  {}", path.display());
+        }
+        for problem in &found {
+            println!("{}", problem);
+        }
+        problems += found.len();
+    }
+
+    if checked == 0 {
+        println!("No config or hooks files found.");
+    }
+    if problems > 0 {
+        return Err(Box::new(GitError::new(format!(
+            "{} problem(s) found",
+            problems
+        ))));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn set(content: &str, key: &str, raw: &str) -> String {
+        let mut doc = content.parse::<DocumentMut>().unwrap();
+        set_in_document(&mut doc, key, parse_value(key, raw).unwrap()).unwrap();
+        doc.to_string()
+    }
+
+    #[test]
+    fn test_set_keeps_comments_and_layout() {
+        let content = "# my settings\nkeep_subdir = true # land in subdirs\n\n[env]\n# ports\nPORT = \"3000\"\n";
+        assert_eq!(
+            set(content, "keep_subdir", "false"),
+            "# my settings\nkeep_subdir = false # land in subdirs\n\n[env]\n# ports\nPORT = \"3000\"\n"
+        );
+        assert_eq!(
+            set(content, "env.PORT", "4000"),
+            "# my settings\nkeep_subdir = true # land in subdirs\n\n[env]\n# ports\nPORT = \"4000\"\n"
+        );
+    }
+
+    #[test]
+    fn test_set_adds_keys_before_tables() {
+        let out = set("[env]\nA = \"1\"\n", "trash_days", "30");
+        let out = set(&out, "env.B", "2");
+        assert_eq!(out, "trash_days = 30\n[env]\nA = \"1\"\nB = \"2\"\n");
+        assert_eq!(set("", "env.A", "x"), "[env]\nA = \"x\"\n");
+    }
+
+    #[test]
+    fn test_parse_value_checks_types_and_keys() {
+        assert!(parse_value("keep_subdir", "yes").is_err());
+        assert!(parse_value("trash_days", "-1").is_err());
+        assert!(parse_value("trash_days", "7").is_ok());
+        assert!(parse_value("worktree_bsae", "wt").is_err());
+        assert!(parse_value("env.1BAD", "x").is_err());
+        assert!(parse_value("env.GOOD", "x").is_ok());
+    }
+
+    #[test]
+    fn test_collect_settings_records_origin() {
+        let tmp = TempDir::new().unwrap();
+        let global = tmp.path().join("global.toml");
+        let repo = tmp.path().join("repo.toml");
+        fs::write(
+            &global,
+            "trash_days = 7\nkeep_subdir = false\n[env]\nA = \"g\"\n",
+        )
+        .unwrap();
+        fs::write(&repo, "trash_days = 30\n[env]\nB = \"r\"\n").unwrap();
+
+        let settings = collect_settings(&[global.clone(), repo.clone()]);
+        assert_eq!(
+            settings["trash_days"],
+            (toml::Value::Integer(30), repo.clone())
+        );
+        assert_eq!(
+            settings["keep_subdir"],
+            (toml::Value::Boolean(false), global.clone())
+        );
+        assert_eq!(settings["env.A"].1, global);
+        assert_eq!(settings["env.B"].1, repo);
+    }
+
+    #[test]
+    fn test_file_problems_config() {
+        let path = Path::new("config.toml");
+        assert!(file_problems(path, "trash_days = 7\n", FileKind::Config).is_empty());
+        assert_eq!(
+            file_problems(
+                path,
+                "keep_subdir = true\nworktree_bse = \"wt\"\n",
+                FileKind::Config
+            ),
+            vec![
+                "config.toml:2:1: unknown field `worktree_bse`, expected one of \
+                  `worktree_base`, `keep_subdir`, `list_format`, `trash_days`, `env`"
+            ]
+        );
+        assert_eq!(
+            file_problems(path, "trash_days = \"soon\"\n", FileKind::Config),
+            vec!["config.toml:1:14: invalid type: string \"soon\", expected u64"]
+        );
+        let problems = file_problems(
+            path,
+            "worktree_base = \"{hub}/{branch}\"\n[env]\n\"1X\" = \"y\"\n",
+            FileKind::Config,
+        );
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("config.toml: worktree_base: "));
+        assert_eq!(
+            problems[1],
+            "config.toml: env: '1X' is not a valid variable name"
+        );
+    }
+
+    #[test]
+    fn test_edit_content_refuses_invalid_results() {
+        let path = Path::new("config.toml");
+        assert_eq!(
+            edit_content(path, "# mine\ntrash_days = 7\n", "trash_days", "30").unwrap(),
+            "# mine\ntrash_days = 30\n"
+        );
+        let err = edit_content(path, "", "worktree_base", "{branch}").unwrap_err();
+        assert!(
+            err.message.contains("unknown placeholder {branch}"),
+            "{}",
+            err
+        );
+        let err = edit_content(path, "", "list_format", "{nme}").unwrap_err();
+        assert!(err.message.contains("list_format: "), "{}", err);
+        assert!(edit_content(path, "bogus = 1\n", "trash_days", "3").is_err());
+    }
+
+    #[test]
+    fn test_collect_settings_skips_what_load_config_ignores() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+        fs::write(
+            &path,
+            "worktree_bse = \"x\"\ntrash_days = \"soon\"\nkeep_subdir = false\n",
+        )
+        .unwrap();
+        let settings = collect_settings(std::slice::from_ref(&path));
+        assert_eq!(settings.keys().collect::<Vec<_>>(), vec!["keep_subdir"]);
+    }
+
+    #[test]
+    fn test_file_problems_hooks() {
+        let path = Path::new("hooks.toml");
+        assert!(file_problems(path, "[create]\npost = [\"make\"]\n", FileKind::Hooks).is_empty());
+        let problems = file_problems(path, "[create]\npost = \"make\"\n", FileKind::Hooks);
+        assert_eq!(problems.len(), 1);
+        assert!(
+            problems[0].starts_with("hooks.toml:2:8: "),
+            "{}",
+            problems[0]
+        );
+    }
+}
@@ -1,7 +1,10 @@
-use crate::git::{detect_repo, get_current_worktree_name, get_worktree_list, run_git_in_dir};
+use crate::cd;
+use crate::dry_run;
+use crate::git::{detect_repo, get_current_worktree_name, get_worktree_list, run_git_mut};
 use crate::gitignore::ensure_gitignore_entry;
 use crate::hooks::{load_hooks, run_post_hooks, run_pre_hooks, HookContext};
-use crate::state::save_previous_worktree;
+use crate::state::record_switch;
+use crate::worktree_env;
 
 pub fn run(
     name: &str,
@@ -17,8 +20,8 @@ pub fn run(
     // Standard-mode lazy init: ensure the worktree parent dir exists, the state
     // dir exists, and worktree_base is gitignored. No-op in bare mode.
     if ctx.is_standard() {
-        std::fs::create_dir_all(&worktree_base)?;
-        std::fs::create_dir_all(ctx.state_dir())?;
+        dry_run::create_dir_all(&worktree_base)?;
+        dry_run::create_dir_all(&ctx.state_dir())?;
         ensure_gitignore_entry(&ctx)?;
     }
 
@@ -47,9 +50,10 @@ pub fn run(
     };
 
     // Load and run pre-hooks
-    let hooks = load_hooks(&ctx);
+    let hooks = load_hooks(&ctx)?;
     let ctx_branch = checkout.or(base.map(|_| name));
-    let context = HookContext::new("create", name, &worktree_path, &anchor, ctx_branch);
+    let context = HookContext::new("create", name, &worktree_path, &anchor, ctx_branch)
+        .with_exports(worktree_env::hook_store_path(&ctx, &worktree_path));
     run_pre_hooks(&hooks, &context)?;
 
     // Pass the resolved absolute path to git worktree add rather than relying on
@@ -61,26 +65,33 @@ pub fn run(
         (None, None) => vec!["worktree", "add", wt_path.as_ref()],
     };
 
-    run_git_in_dir(&anchor, &args)?;
+    run_git_mut(&anchor, &args)?;
 
     // Run post-hooks (from worktree directory)
     run_post_hooks(&hooks, &context);
 
     if switch {
         // Save current worktree as previous (if we were in a worktree)
-        if let Some(ref current) = current_worktree {
-            save_previous_worktree(&ctx, current)?;
-        }
-        // Print only the path for shell wrapper to cd into
-        println!("{}", worktree_path.display());
-    } else {
-        println!("Created worktree '{}' at {}", name, worktree_path.display());
+        record_switch(&ctx, current_worktree.as_deref(), name)?;
+    }
+    // Without the WT_CD_FILE handoff, `-s` reserves stdout for the path alone
+    if !switch || cd::handoff_active() {
+        println!(
+            "{} worktree '{}' at {}",
+            dry_run::label("Created", "Would create"),
+            name,
+            worktree_path.display()
+        );
         if let Some(source) = base {
             println!("Branched from worktree: {}", source);
         } else if let Some(b) = checkout {
             println!("Checked out branch: {}", b);
         }
     }
+    // The new worktree does not exist after a dry run
+    if switch && !dry_run::is_enabled() {
+        cd::request(&worktree_path)?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,437 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+use crate::commands::list::align_columns;
+use crate::commands::prune::get_worktrees_to_prune;
+use crate::git::{self, detect_repo, get_worktree_list, run_git_in_dir, GitError};
+use crate::parallel::{default_jobs, parallel_map};
+
+/// On-disk usage of one worktree, split by what the bytes are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Files tracked by git.
+    pub tracked: u64,
+    /// Ignored files (build output, `node_modules`, ...).
+    pub ignored: u64,
+    /// Untracked files that are not ignored.
+    pub untracked: u64,
+    /// Hardlinked files (link count > 1), counted once per worktree whatever
+    /// their category, since removing this worktree alone does not free them.
+    pub shared: u64,
+}
+
+impl DiskUsage {
+    pub fn total(&self) -> u64 {
+        self.exclusive() + self.shared
+    }
+
+    /// Bytes that removing the worktree actually frees (everything but `shared`).
+    pub fn exclusive(&self) -> u64 {
+        self.tracked + self.ignored + self.untracked
+    }
+}
+
+/// What `measure_worktree` found: the usage, and the size of every hardlinked
+/// file by `(device, inode)`, so totals over several worktrees count a file
+/// linked into more than one of them once.
+#[derive(Debug, Default)]
+pub struct Measurement {
+    pub usage: DiskUsage,
+    links: HashMap<(u64, u64), u64>,
+}
+
+/// Usage of several worktrees together.
+fn combine<'a>(measurements: impl IntoIterator<Item = &'a Measurement>) -> DiskUsage {
+    let mut total = DiskUsage::default();
+    let mut links = HashMap::new();
+    for m in measurements {
+        total.tracked += m.usage.tracked;
+        total.ignored += m.usage.ignored;
+        total.untracked += m.usage.untracked;
+        links.extend(&m.links);
+    }
+    total.shared = links.values().sum();
+    total
+}
+
+/// Column `wt du --sort` orders by. Sizes sort largest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    Name,
+    Total,
+    Tracked,
+    Ignored,
+    Untracked,
+    Shared,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Tracked,
+    Ignored,
+    Untracked,
+}
+
+/// Bytes a file occupies on disk (allocated blocks, not apparent length).
+#[cfg(unix)]
+fn disk_size(meta: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn disk_size(meta: &Metadata) -> u64 {
+    meta.len()
+}
+
+/// `(device, inode)` of a file with more than one hard link.
+#[cfg(unix)]
+fn hardlink_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (meta.is_file() && meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn hardlink_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Split NUL-separated `git ls-files -z` output into relative paths, dropping
+/// the trailing `/` git adds to directories.
+fn parse_ls_files(output: &str) -> HashSet<PathBuf> {
+    output
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(|p| PathBuf::from(p.trim_end_matches('/')))
+        .collect()
+}
+
+struct Walker<'a> {
+    tracked: HashSet<PathBuf>,
+    ignored: HashSet<PathBuf>,
+    /// Absolute paths never descended into (nested worktrees).
+    skip: &'a [PathBuf],
+    links: HashMap<(u64, u64), u64>,
+    usage: DiskUsage,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, root: &Path, rel: &Path, inherited: Option<Category>) {
+        let Ok(entries) = fs::read_dir(root.join(rel)) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            // The worktree's `.git` (a file, or the repository itself in the
+            // main worktree) is repository storage, not worktree content.
+            if rel.as_os_str().is_empty() && name == ".git" {
+                continue;
+            }
+            let abs = entry.path();
+            if self.skip.contains(&abs) {
+                continue;
+            }
+            let Ok(meta) = fs::symlink_metadata(&abs) else {
+                continue;
+            };
+            let child = rel.join(&name);
+            let category = inherited.or_else(|| {
+                if self.tracked.contains(&child) {
+                    Some(Category::Tracked)
+                } else if self.ignored.contains(&child) {
+                    Some(Category::Ignored)
+                } else {
+                    None
+                }
+            });
+
+            if meta.is_dir() {
+                self.walk(root, &child, category);
+                continue;
+            }
+
+            let size = disk_size(&meta);
+            if let Some(id) = hardlink_id(&meta) {
+                if self.links.insert(id, size).is_none() {
+                    self.usage.shared += size;
+                }
+                continue;
+            }
+            match category.unwrap_or(Category::Untracked) {
+                Category::Tracked => self.usage.tracked += size,
+                Category::Ignored => self.usage.ignored += size,
+                Category::Untracked => self.usage.untracked += size,
+            }
+        }
+    }
+}
+
+/// Measure the worktree at `path`. `skip` holds absolute paths of worktrees
+/// nested inside it (standard layout), which are measured on their own.
+pub fn measure_worktree(path: &Path, skip: &[PathBuf]) -> Result<Measurement, GitError> {
+    let tracked = run_git_in_dir(path, &["ls-files", "-z"])?;
+    let ignored = run_git_in_dir(
+        path,
+        &[
+            "ls-files",
+            "-z",
+            "--others",
+            "--ignored",
+            "--exclude-standard",
+            "--directory",
+        ],
+    )?;
+
+    let mut walker = Walker {
+        tracked: parse_ls_files(&tracked),
+        ignored: parse_ls_files(&ignored),
+        skip,
+        links: HashMap::new(),
+        usage: DiskUsage::default(),
+    };
+    walker.walk(path, Path::new(""), None);
+    Ok(Measurement {
+        usage: walker.usage,
+        links: walker.links,
+    })
+}
+
+/// Human-readable binary size (`0 B`, `512 B`, `1.5 KiB`, `12.0 MiB`, ...).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Sort `(name, usage)` rows in place by `key`; sizes sort largest first, ties
+/// broken by name.
+pub fn sort_rows(rows: &mut [(String, DiskUsage)], key: SortKey) {
+    let size = |u: &DiskUsage| match key {
+        SortKey::Name => 0,
+        SortKey::Total => u.total(),
+        SortKey::Tracked => u.tracked,
+        SortKey::Ignored => u.ignored,
+        SortKey::Untracked => u.untracked,
+        SortKey::Shared => u.shared,
+    };
+    rows.sort_by(|(an, au), (bn, bu)| size(bu).cmp(&size(au)).then_with(|| an.cmp(bn)));
+}
+
+pub fn run(sort: Option<SortKey>) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let anchor = ctx.anchor_dir().to_path_buf();
+    let worktrees: Vec<_> = get_worktree_list(&anchor)?
+        .into_iter()
+        .filter(|wt| wt.head != "(bare)" && wt.path.is_dir())
+        .collect();
+
+    if worktrees.is_empty() {
+        println!("No worktrees found.");
+        return Ok(());
+    }
+
+    let paths: Vec<PathBuf> = worktrees.iter().map(|wt| wt.path.clone()).collect();
+    let usages = parallel_map(&worktrees, default_jobs(), |wt| {
+        let nested: Vec<PathBuf> = paths
+            .iter()
+            .filter(|p| **p != wt.path && p.starts_with(&wt.path))
+            .cloned()
+            .collect();
+        measure_worktree(&wt.path, &nested)
+    });
+
+    let mut measured: Vec<(String, Measurement)> = Vec::new();
+    for (wt, usage) in worktrees.iter().zip(usages) {
+        let name = wt
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| wt.path.display().to_string());
+        match usage {
+            Ok(m) => measured.push((name, m)),
+            Err(e) => eprintln!("Warning: cannot measure '{}': {}", name, e),
+        }
+    }
+    let mut rows: Vec<(String, DiskUsage)> = measured
+        .iter()
+        .map(|(name, m)| (name.clone(), m.usage))
+        .collect();
+    if let Some(key) = sort {
+        sort_rows(&mut rows, key);
+    }
+
+    let mut table = vec![
+        ["NAME", "TRACKED", "IGNORED", "UNTRACKED", "SHARED", "TOTAL"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>(),
+    ];
+    for (name, usage) in &rows {
+        table.push(usage_row(name, usage));
+    }
+    table.push(usage_row(
+        "total",
+        &combine(measured.iter().map(|(_, m)| m)),
+    ));
+    for line in align_columns(&table) {
+        println!("{}", line);
+    }
+
+    // What `wt prune` would remove: the same keep-set as `commands::prune`.
+    let all = get_worktree_list(&anchor)?;
+    let keep_branch = if ctx.is_standard() {
+        None
+    } else {
+        git::get_default_branch(&anchor)
+    };
+    let targets = get_worktrees_to_prune(&all, keep_branch.as_deref(), ctx.main_worktree());
+    let target_names: Vec<&String> = targets.iter().map(|(n, _)| n).collect();
+    let freed = combine(
+        measured
+            .iter()
+            .filter(|(name, _)| target_names.contains(&name))
+            .map(|(_, m)| m),
+    );
+    println!();
+    println!(
+        "'wt prune' would free {} across {} worktree(s){}.",
+        format_size(freed.exclusive()),
+        targets.len(),
+        if freed.shared > 0 {
+            format!(
+                " (plus up to {} of shared hardlinked data)",
+                format_size(freed.shared)
+            )
+        } else {
+            String::new()
+        }
+    );
+
+    Ok(())
+}
+
+fn usage_row(name: &str, usage: &DiskUsage) -> Vec<String> {
+    vec![
+        name.to_string(),
+        format_size(usage.tracked),
+        format_size(usage.ignored),
+        format_size(usage.untracked),
+        format_size(usage.shared),
+        format_size(usage.total()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::git;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_sort_rows_by_size_then_name() {
+        let small = DiskUsage {
+            ignored: 10,
+            ..Default::default()
+        };
+        let big = DiskUsage {
+            ignored: 100,
+            ..Default::default()
+        };
+        let mut rows = vec![
+            ("b".to_string(), small),
+            ("c".to_string(), big),
+            ("a".to_string(), small),
+        ];
+        sort_rows(&mut rows, SortKey::Ignored);
+        let names: Vec<&str> = rows.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["c", "a", "b"]);
+
+        sort_rows(&mut rows, SortKey::Name);
+        let names: Vec<&str> = rows.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_measure_worktree_categories() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        git(root, &["init", "-q"]);
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("tracked.txt"), "x".repeat(10_000)).unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("target/debug/out.bin"), "y".repeat(20_000)).unwrap();
+        fs::write(root.join("notes.txt"), "z".repeat(10_000)).unwrap();
+        fs::write(root.join("linked.bin"), "w".repeat(10_000)).unwrap();
+        fs::hard_link(
+            root.join("linked.bin"),
+            tmp.path().join("target/linked.bin"),
+        )
+        .unwrap();
+
+        let usage = measure_worktree(root, &[]).unwrap().usage;
+        assert!(usage.tracked > 0);
+        assert!(usage.ignored > 0);
+        assert!(usage.untracked > 0);
+        if cfg!(unix) {
+            // Both links of the same inode count once, as shared.
+            assert!(usage.shared > 0);
+            assert!(usage.shared < usage.ignored);
+        }
+        assert_eq!(usage.total(), usage.exclusive() + usage.shared);
+    }
+
+    #[test]
+    fn test_measure_worktree_skips_nested_paths() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        git(root, &["init", "-q"]);
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested/big.bin"), "n".repeat(50_000)).unwrap();
+
+        let skipped = measure_worktree(root, &[root.join("nested")]).unwrap();
+        assert_eq!(skipped.usage, DiskUsage::default());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_combine_counts_links_across_worktrees_once() {
+        let tmp = TempDir::new().unwrap();
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        for dir in [&a, &b] {
+            fs::create_dir_all(dir).unwrap();
+            git(dir, &["init", "-q"]);
+            fs::write(dir.join("own.txt"), "o".repeat(10_000)).unwrap();
+        }
+        fs::write(a.join("shared.bin"), "s".repeat(50_000)).unwrap();
+        fs::hard_link(a.join("shared.bin"), b.join("shared.bin")).unwrap();
+
+        let ma = measure_worktree(&a, &[]).unwrap();
+        let mb = measure_worktree(&b, &[]).unwrap();
+        assert!(ma.usage.shared > 0);
+        assert_eq!(ma.usage.shared, mb.usage.shared);
+
+        let total = combine([&ma, &mb]);
+        assert_eq!(total.shared, ma.usage.shared);
+        assert_eq!(total.untracked, ma.usage.untracked + mb.usage.untracked);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+
+use crate::commands::list::align_columns;
+use crate::commands::prompt::locate;
+use crate::commands::remove::resolve_worktree;
+use crate::config::load_config;
+use crate::dry_run;
+use crate::git::{detect_repo, find_worktree_containing, get_worktree_list, GitError, RepoContext};
+use crate::worktree_env::{is_valid_key, load_store, plan, resolve, save_store, worktree_key, Op};
+
+/// Shells `wt env activate` can print commands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EnvShell {
+    Bash,
+    Zsh,
+    Fish,
+    Nu,
+    Pwsh,
+}
+
+/// The worktree `name` names, or the one containing the current directory.
+fn target_worktree(
+    ctx: &RepoContext,
+    name: Option<&str>,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let worktrees = get_worktree_list(ctx.anchor_dir())?;
+    let found = match name {
+        Some(name) => resolve_worktree(&worktrees, ctx, name).map_err(GitError::new)?,
+        None => {
+            let current_dir = std::env::current_dir()?;
+            let current_dir = current_dir.canonicalize().unwrap_or(current_dir);
+            find_worktree_containing(&worktrees, &current_dir)
+                .ok_or_else(|| GitError::new("Not inside a worktree; pass --worktree <name>"))?
+        }
+    };
+    Ok(found.path.clone())
+}
+
+/// Store `KEY=VALUE` pairs for a worktree.
+pub fn run_set(pairs: &[String], worktree: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let key = worktree_key(&target_worktree(&ctx, worktree)?);
+
+    let mut vars = load_store(&ctx, &key);
+    for pair in pairs {
+        let (name, value) = pair
+            .split_once('=')
+            .filter(|(name, _)| is_valid_key(name))
+            .ok_or_else(|| GitError::new(format!("Expected KEY=VALUE, got '{}'", pair)))?;
+        vars.insert(name.to_string(), value.to_string());
+    }
+    save_store(&ctx, &key, &vars)?;
+    println!(
+        "{} {} variable(s) for '{}'.",
+        dry_run::label("Set", "Would set"),
+        pairs.len(),
+        key
+    );
+    Ok(())
+}
+
+/// Remove variables stored with `wt env set`.
+pub fn run_unset(
+    names: &[String],
+    worktree: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let key = worktree_key(&target_worktree(&ctx, worktree)?);
+
+    let mut vars = load_store(&ctx, &key);
+    for name in names {
+        if vars.remove(name).is_none() {
+            eprintln!("Warning: '{}' is not set for '{}'", name, key);
+        }
+    }
+    save_store(&ctx, &key, &vars)?;
+    Ok(())
+}
+
+/// Show a worktree's variables and where each comes from.
+pub fn run_list(worktree: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let path = target_worktree(&ctx, worktree)?;
+    let key = worktree_key(&path);
+    let vars = resolve(&ctx, &load_config(&ctx), &key, &path);
+
+    if vars.is_empty() {
+        println!("No variables for '{}'.", key);
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = vars
+        .iter()
+        .map(|(name, (value, source))| {
+            vec![
+                format!("{}={}", name, value),
+                format!("({})", source.label()),
+            ]
+        })
+        .collect();
+    for line in align_columns(&rows) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Render `ops` as commands for `shell` to evaluate. Nushell cannot evaluate
+/// strings, so it gets a JSON record its wrapper applies with `load-env`.
+pub fn render(ops: &[Op], shell: EnvShell) -> String {
+    if shell == EnvShell::Nu {
+        let mut set = serde_json::Map::new();
+        let mut unset = Vec::new();
+        for op in ops {
+            match op {
+                Op::Set(k, v) => {
+                    set.insert(k.clone(), v.clone().into());
+                }
+                Op::Unset(k) => unset.push(serde_json::Value::from(k.clone())),
+            }
+        }
+        return serde_json::json!({ "set": set, "unset": unset }).to_string() + "\n";
+    }
+
+    ops.iter()
+        .map(|op| match (shell, op) {
+            (EnvShell::Fish, Op::Set(k, v)) => format!(
+                "set -gx {} '{}'\n",
+                k,
+                v.replace('\\', r"\\").replace('\'', r"\'")
+            ),
+            (EnvShell::Fish, Op::Unset(k)) => format!("set -e {}\n", k),
+            (EnvShell::Pwsh, Op::Set(k, v)) => {
+                format!("$env:{} = '{}'\n", k, v.replace('\'', "''"))
+            }
+            (EnvShell::Pwsh, Op::Unset(k)) => {
+                format!("Remove-Item Env:{} -ErrorAction SilentlyContinue\n", k)
+            }
+            (_, Op::Set(k, v)) => format!("export {}={}\n", k, posix_quote(v)),
+            (_, Op::Unset(k)) => format!("unset {}\n", k),
+        })
+        .collect()
+}
+
+/// Print the commands that move the shell's environment to the worktree
+/// containing the current directory (or clear it outside any worktree).
+/// Never fails, so the shell wrapper can always evaluate the output.
+pub fn run_activate(shell: EnvShell) -> Result<(), Box<dyn std::error::Error>> {
+    let mut target = BTreeMap::new();
+    let current_dir = std::env::current_dir().ok();
+    let root = current_dir
+        .as_deref()
+        .and_then(locate)
+        .and_then(|(_, root)| root);
+    if let (Some(root), Ok(ctx)) = (root, detect_repo()) {
+        let key = worktree_key(&root);
+        target = resolve(&ctx, &load_config(&ctx), &key, &root)
+            .into_iter()
+            .map(|(name, (value, _))| (name, value))
+            .collect();
+    }
+
+    let ops = plan(&|key| std::env::var(key).ok(), &target);
+    print!("{}", render(&ops, shell));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops() -> Vec<Op> {
+        vec![
+            Op::Set("MSG".to_string(), "it's here".to_string()),
+            Op::Unset("OLD".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_render_posix() {
+        assert_eq!(
+            render(&ops(), EnvShell::Bash),
+            "export MSG='it'\\''s here'\nunset OLD\n"
+        );
+        assert_eq!(
+            render(&ops(), EnvShell::Zsh),
+            render(&ops(), EnvShell::Bash)
+        );
+    }
+
+    #[test]
+    fn test_render_fish_and_pwsh() {
+        assert_eq!(
+            render(&ops(), EnvShell::Fish),
+            "set -gx MSG 'it\\'s here'\nset -e OLD\n"
+        );
+        assert_eq!(
+            render(&ops(), EnvShell::Pwsh),
+            "$env:MSG = 'it''s here'\nRemove-Item Env:OLD -ErrorAction SilentlyContinue\n"
+        );
+    }
+
+    #[test]
+    fn test_render_nu_json() {
+        let out = render(&ops(), EnvShell::Nu);
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value["set"]["MSG"], "it's here");
+        assert_eq!(value["unset"][0], "OLD");
+    }
+
+    #[test]
+    fn test_render_nothing() {
+        assert_eq!(render(&[], EnvShell::Bash), "");
+        assert_eq!(render(&[], EnvShell::Nu), "{\"set\":{},\"unset\":[]}\n");
+    }
+}
@@ -0,0 +1,319 @@
+use std::path::{Path, PathBuf};
+
+use crate::confirm::confirm;
+use crate::dry_run;
+use crate::git::{detect_repo, get_worktree_list, run_git_mut, Worktree};
+use crate::state::{load_state, save_state};
+use crate::worktree_env;
+
+/// What an unregistered directory under `worktree_base` turns out to be.
+#[derive(Debug, PartialEq, Eq)]
+enum Unregistered {
+    /// Left behind by one of this repository's worktrees: its `.git` file
+    /// points into `<git_dir>/worktrees/`, or it is empty.
+    Orphan,
+    /// A plain directory, which may hold leftover worktrees further down
+    /// (branch names with slashes).
+    Search,
+    /// Anything else: a checkout of another repository, or the user's own files.
+    Keep,
+}
+
+fn classify(dir: &Path, git_dir: &Path) -> Unregistered {
+    let dot_git = dir.join(".git");
+    if dot_git.is_file() {
+        let Ok(content) = std::fs::read_to_string(&dot_git) else {
+            return Unregistered::Keep;
+        };
+        let Some(target) = content.trim().strip_prefix("gitdir: ") else {
+            return Unregistered::Keep;
+        };
+        let target = dir.join(target);
+        let admin = git_dir.join("worktrees");
+        // The admin entry is usually gone by now, so compare both the paths as
+        // written and their canonical forms (e.g. /tmp -> /private/tmp)
+        let canon_admin = git_dir
+            .canonicalize()
+            .map(|d| d.join("worktrees"))
+            .unwrap_or_else(|_| admin.clone());
+        let canon_target = target
+            .parent()
+            .and_then(|p| p.canonicalize().ok())
+            .zip(target.file_name())
+            .map(|(p, n)| p.join(n))
+            .unwrap_or_else(|| target.clone());
+        return if target.starts_with(&admin) || canon_target.starts_with(&canon_admin) {
+            Unregistered::Orphan
+        } else {
+            Unregistered::Keep
+        };
+    }
+    if dot_git.exists() {
+        return Unregistered::Keep;
+    }
+    match std::fs::read_dir(dir).map(|mut entries| entries.next().is_none()) {
+        Ok(true) => Unregistered::Orphan,
+        Ok(false) => Unregistered::Search,
+        Err(_) => Unregistered::Keep,
+    }
+}
+
+/// Whether `base` can be searched for orphans at all: not when it is, or
+/// contains, the main worktree, whose own directories would all be candidates.
+fn scan_allowed(base: &Path, main_worktree: Option<&Path>) -> bool {
+    let canon = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    main_worktree.is_none_or(|main| !canon(main).starts_with(canon(base)))
+}
+
+/// Directories under `base` left behind by this repository's worktrees: those
+/// whose `.git` file points into `<git_dir>/worktrees/` but that git no longer
+/// lists, and empty directories. Plain directories are searched, never
+/// reported, and hidden entries (`.bare`, `.wtree`, `.git`) are skipped.
+pub fn find_orphans(base: &Path, worktrees: &[Worktree], git_dir: &Path) -> Vec<PathBuf> {
+    let registered: Vec<PathBuf> = worktrees
+        .iter()
+        .map(|wt| wt.path.canonicalize().unwrap_or_else(|_| wt.path.clone()))
+        .collect();
+
+    let mut orphans = Vec::new();
+    let mut pending = vec![base.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            if !is_dir || entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let canon = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if registered.contains(&canon) {
+                continue;
+            }
+            if registered.iter().any(|r| r.starts_with(&canon)) {
+                pending.push(path);
+                continue;
+            }
+            match classify(&path, git_dir) {
+                Unregistered::Orphan => orphans.push(path),
+                Unregistered::Search => pending.push(path),
+                Unregistered::Keep => {}
+            }
+        }
+    }
+    orphans.sort();
+    orphans
+}
+
+/// Stale admin entries: registered worktrees git reports as prunable.
+fn stale_entries(worktrees: &[Worktree]) -> Vec<&Worktree> {
+    worktrees
+        .iter()
+        .filter(|wt| wt.prunable.is_some())
+        .collect()
+}
+
+/// Clean up after worktrees that went away behind wtree's back: prune stale
+/// admin entries, drop switch history and env stores for missing worktrees,
+/// and offer to delete orphan directories under `worktree_base`.
+pub fn run(force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let anchor = ctx.anchor_dir().to_path_buf();
+    let worktrees = get_worktree_list(&anchor)?;
+
+    let stale = stale_entries(&worktrees);
+    let base = ctx.worktree_base();
+    let orphans = if scan_allowed(&base, ctx.main_worktree()) {
+        find_orphans(&base, &worktrees, &ctx.git_dir())
+    } else {
+        eprintln!(
+            "Note: not looking for orphan directories: worktree_base {} contains the main worktree.",
+            base.display()
+        );
+        Vec::new()
+    };
+
+    let live: Vec<String> = worktrees
+        .iter()
+        .filter(|wt| wt.prunable.is_none() && wt.head != "(bare)")
+        .filter_map(|wt| wt.path.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .collect();
+    let mut state = load_state(&ctx)?;
+    let dropped = state.retain_history(|name| live.iter().any(|l| l == name));
+    let stale_env: Vec<String> = worktree_env::stored_names(&ctx)
+        .into_iter()
+        .filter(|name| !live.contains(name))
+        .collect();
+
+    if stale.is_empty() && orphans.is_empty() && dropped.is_empty() && stale_env.is_empty() {
+        println!("Nothing to clean up.");
+        return Ok(());
+    }
+
+    if !stale.is_empty() {
+        println!("Stale worktree entries ('git worktree prune'):");
+        for wt in &stale {
+            match wt.prunable.as_deref().filter(|r| !r.is_empty()) {
+                Some(reason) => println!("  - {} ({})", wt.path.display(), reason),
+                None => println!("  - {}", wt.path.display()),
+            }
+        }
+        run_git_mut(&anchor, &["worktree", "prune"])?;
+    }
+
+    if !dropped.is_empty() {
+        println!("Switch history entries for missing worktrees:");
+        for name in &dropped {
+            println!("  - {}", name);
+        }
+        save_state(&ctx, &state)?;
+    }
+
+    if !stale_env.is_empty() {
+        println!("Environment variables of missing worktrees:");
+        for name in &stale_env {
+            println!("  - {}", name);
+            worktree_env::forget(&ctx, name)?;
+        }
+    }
+
+    if !orphans.is_empty() {
+        println!("Orphan directories git does not know about:");
+        for path in &orphans {
+            println!("  - {}", path.display());
+        }
+        let delete = force
+            || dry_run::is_enabled()
+            || confirm(&format!("Delete {} orphan directory(ies)?", orphans.len()))?;
+        if delete {
+            for path in &orphans {
+                if let Err(e) = dry_run::remove_dir_all(path) {
+                    eprintln!("Warning: failed to delete {}: {}", path.display(), e);
+                }
+            }
+        } else {
+            println!("Kept orphan directories.");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn worktree(path: PathBuf) -> Worktree {
+        Worktree {
+            path,
+            head: "abc123".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path();
+        for dir in [".bare", "main", "feature/foo", "feature/bar", "leftover"] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+        }
+        fs::write(base.join("notes.txt"), "").unwrap();
+        let worktrees = vec![
+            worktree(base.join("main")),
+            worktree(base.join("feature/foo")),
+        ];
+
+        let orphans = find_orphans(base, &worktrees, &base.join(".bare"));
+        assert_eq!(
+            orphans,
+            vec![base.join("feature/bar"), base.join("leftover")]
+        );
+    }
+
+    #[test]
+    fn test_find_orphans_skips_other_repositories() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path();
+        let git_dir = base.join("ours/.bare");
+        for dir in [
+            "ours/.bare/worktrees/stale",
+            "other-repo/.git",
+            "theirs",
+            "stale",
+        ] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+        }
+        fs::write(
+            base.join("theirs/.git"),
+            "gitdir: /elsewhere/.git/worktrees/theirs\n",
+        )
+        .unwrap();
+        fs::write(
+            base.join("stale/.git"),
+            format!("gitdir: {}\n", git_dir.join("worktrees/stale").display()),
+        )
+        .unwrap();
+        let worktrees = vec![worktree(base.join("ours"))];
+
+        assert_eq!(
+            find_orphans(base, &worktrees, &git_dir),
+            vec![base.join("stale")]
+        );
+    }
+
+    #[test]
+    fn test_find_orphans_never_reports_plain_directories() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path();
+        let git_dir = base.join(".bare");
+        for dir in [".bare/worktrees", "main", "scratch/notes", "feature/gone"] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+        }
+        fs::write(base.join("scratch/notes/todo.txt"), "").unwrap();
+        fs::write(base.join("scratch/keep.txt"), "").unwrap();
+        fs::write(
+            base.join("feature/gone/.git"),
+            format!("gitdir: {}\n", git_dir.join("worktrees/gone").display()),
+        )
+        .unwrap();
+        fs::write(base.join("feature/gone/main.rs"), "").unwrap();
+        let worktrees = vec![worktree(base.join("main"))];
+
+        assert_eq!(
+            find_orphans(base, &worktrees, &git_dir),
+            vec![base.join("feature/gone")]
+        );
+    }
+
+    #[test]
+    fn test_scan_refused_when_base_contains_main_worktree() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().join("repo");
+        fs::create_dir_all(main.join(".worktrees")).unwrap();
+        assert!(!scan_allowed(&main, Some(&main)));
+        assert!(!scan_allowed(tmp.path(), Some(&main)));
+        assert!(scan_allowed(&main.join(".worktrees"), Some(&main)));
+        assert!(scan_allowed(tmp.path(), None));
+    }
+
+    #[test]
+    fn test_find_orphans_missing_base() {
+        let tmp = TempDir::new().unwrap();
+        assert!(find_orphans(&tmp.path().join("nope"), &[], tmp.path()).is_empty());
+    }
+
+    #[test]
+    fn test_stale_entries() {
+        let mut gone = worktree(PathBuf::from("/hub/gone"));
+        gone.prunable = Some("gitdir file points to non-existent location".to_string());
+        let worktrees = vec![worktree(PathBuf::from("/hub/main")), gone];
+        let stale = stale_entries(&worktrees);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].path, PathBuf::from("/hub/gone"));
+    }
+}
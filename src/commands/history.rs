@@ -0,0 +1,63 @@
+use crate::git::detect_repo;
+use crate::state::{load_state, now_timestamp};
+
+/// Format an elapsed number of seconds as a compact relative age
+/// (`just now`, `5m ago`, `3h ago`, `2d ago`, `6w ago`).
+pub fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86_399 => format!("{}h ago", seconds / 3600),
+        86_400..=1_209_599 => format!("{}d ago", seconds / 86_400),
+        _ => format!("{}w ago", seconds / 604_800),
+    }
+}
+
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let state = load_state(&ctx)?;
+
+    if state.history.is_empty() {
+        println!("No switch history.");
+        return Ok(());
+    }
+
+    let now = now_timestamp();
+    for (i, entry) in state.history.iter().enumerate() {
+        let age = if entry.timestamp == 0 {
+            "unknown".to_string()
+        } else {
+            format_age(now.saturating_sub(entry.timestamp))
+        };
+        // The index matches `wt switch -N`.
+        println!("{:>3}  {:<20} {}", i + 1, entry.name, age);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age_seconds() {
+        assert_eq!(format_age(0), "just now");
+        assert_eq!(format_age(59), "just now");
+    }
+
+    #[test]
+    fn test_format_age_minutes_and_hours() {
+        assert_eq!(format_age(60), "1m ago");
+        assert_eq!(format_age(3599), "59m ago");
+        assert_eq!(format_age(3600), "1h ago");
+        assert_eq!(format_age(86_399), "23h ago");
+    }
+
+    #[test]
+    fn test_format_age_days_and_weeks() {
+        assert_eq!(format_age(86_400), "1d ago");
+        assert_eq!(format_age(13 * 86_400), "13d ago");
+        assert_eq!(format_age(14 * 86_400), "2w ago");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::dry_run;
 use crate::git::{detect_repo, GitError, Layout};
 use crate::gitignore::ensure_gitignore_entry;
 use crate::hooks_template::default_hooks_content;
@@ -17,21 +18,27 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let state_dir = ctx.state_dir();
-    std::fs::create_dir_all(&state_dir)
+    dry_run::create_dir_all(&state_dir)
         .map_err(|e| GitError::new(format!("Failed to create state directory: {}", e)))?;
 
     // Write the hooks template only if absent, so re-running stays idempotent and
     // never clobbers a customized hooks.toml.
     let hooks_path = state_dir.join("hooks.toml");
     if !hooks_path.exists() {
-        std::fs::write(&hooks_path, default_hooks_content())
+        dry_run::write_file(&hooks_path, &default_hooks_content())
             .map_err(|e| GitError::new(format!("Failed to write hooks.toml: {}", e)))?;
     }
 
     ensure_gitignore_entry(&ctx)?;
 
     let worktree_base = ctx.worktree_base();
-    println!("Initialized wtree for standard repo.");
+    println!(
+        "{}",
+        dry_run::label(
+            "Initialized wtree for standard repo.",
+            "Would initialize wtree for standard repo."
+        )
+    );
     println!("  main worktree: {}", main_worktree.display());
     println!("  state dir:     {}", state_dir.display());
     println!("  hooks:         {}", hooks_path.display());
@@ -1,4 +1,52 @@
-use crate::git::{detect_repo, get_worktree_list};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::commands::history::format_age;
+use crate::config::load_config;
+use crate::git::{
+    detect_repo, find_worktree_containing, get_repo_default_branch, get_worktree_list, RepoContext,
+    Worktree,
+};
+use crate::list_template::{color_enabled, needs_status, parse_template, render};
+use crate::parallel::{default_jobs, parallel_map};
+use crate::state::now_timestamp;
+use crate::status::{collect_status, default_compare_ref, WorktreeStatus};
+
+/// Output format for `wt list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Fixed-width, human-readable columns.
+    Text,
+    /// A JSON array of `ListEntry` objects.
+    Json,
+    /// Stable `key value` lines, one blank-line-separated block per worktree.
+    Porcelain,
+}
+
+/// One worktree as reported by `wt list`. This is the stable schema shared by
+/// the JSON and porcelain outputs; fields are only ever added, never renamed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ListEntry {
+    pub name: String,
+    pub path: PathBuf,
+    /// Short branch name, `None` for a detached HEAD.
+    pub branch: Option<String>,
+    pub head: String,
+    /// The standard-layout primary checkout (never true in the bare layout).
+    pub is_main: bool,
+    /// Checked out on the repository's default branch.
+    pub is_default: bool,
+    /// Contains the current directory.
+    pub is_current: bool,
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    pub prunable: bool,
+    pub prunable_reason: Option<String>,
+    /// Working-tree and branch status, only collected with `--status`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<WorktreeStatus>,
+}
 
 /// Format branch information for display
 /// - If branch is present, strips "refs/heads/" prefix
@@ -12,42 +60,337 @@ pub fn format_branch_info(branch: Option<&str>, head: &str) -> String {
         .unwrap_or_else(|| head.chars().take(7).collect())
 }
 
-pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let ctx = detect_repo()?;
-    let worktrees = get_worktree_list(ctx.anchor_dir())?;
+/// Turn empty lock/prune reasons (git printed the bare keyword) into `None`.
+fn non_empty(reason: Option<&String>) -> Option<String> {
+    reason.filter(|r| !r.is_empty()).cloned()
+}
 
-    if worktrees.is_empty() {
-        println!("No worktrees found.");
-        return Ok(());
+/// Build the list entries, skipping the bare repo entry.
+pub fn collect_entries(
+    worktrees: &[Worktree],
+    main_worktree: Option<&Path>,
+    default_branch: Option<&str>,
+    current_dir: Option<&Path>,
+) -> Vec<ListEntry> {
+    // Canonical main worktree path (standard mode) for the main marker.
+    let main_canon = main_worktree.and_then(|m| m.canonicalize().ok());
+    let default_ref = default_branch.map(|b| format!("refs/heads/{}", b));
+    let current_path = current_dir
+        .and_then(|dir| find_worktree_containing(worktrees, dir))
+        .map(|wt| wt.path.clone());
+
+    worktrees
+        .iter()
+        // Skip the bare repo entry (it carries the "(bare)" head marker).
+        .filter(|wt| wt.head != "(bare)")
+        .map(|wt| {
+            let name = wt
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| wt.path.display().to_string());
+
+            let is_main = match (&main_canon, main_worktree) {
+                (Some(m), _) => wt.path.canonicalize().map(|p| &p == m).unwrap_or(false),
+                (None, Some(m)) => wt.path == m,
+                (None, None) => false,
+            };
+
+            ListEntry {
+                name,
+                path: wt.path.clone(),
+                branch: wt
+                    .branch
+                    .as_deref()
+                    .map(|b| b.strip_prefix("refs/heads/").unwrap_or(b).to_string()),
+                head: wt.head.clone(),
+                is_main,
+                is_default: default_ref.is_some() && wt.branch == default_ref,
+                is_current: current_path.as_ref() == Some(&wt.path),
+                locked: wt.locked.is_some(),
+                lock_reason: non_empty(wt.locked.as_ref()),
+                prunable: wt.prunable.is_some(),
+                prunable_reason: non_empty(wt.prunable.as_ref()),
+                status: None,
+            }
+        })
+        .collect()
+}
+
+/// Fill in `status` for every entry, running the per-worktree `git` calls in
+/// parallel so a hub with dozens of worktrees stays fast.
+pub fn attach_status(ctx: &RepoContext, entries: &mut [ListEntry]) {
+    let default_ref = default_compare_ref(ctx);
+    let statuses = parallel_map(entries, default_jobs(), |entry| {
+        collect_status(&entry.path, default_ref.as_deref())
+    });
+    for (entry, status) in entries.iter_mut().zip(statuses) {
+        entry.status = status;
+    }
+}
+
+/// Compact change summary: `clean`, or the non-zero counts of staged (`+`),
+/// modified (`~`), untracked (`?`) and conflicted (`!`) paths.
+pub fn format_changes(status: &WorktreeStatus) -> String {
+    if !status.is_dirty() {
+        return "clean".to_string();
     }
+    [
+        ('+', status.staged),
+        ('~', status.modified),
+        ('?', status.untracked),
+        ('!', status.conflicted),
+    ]
+    .iter()
+    .filter(|(_, n)| *n > 0)
+    .map(|(sigil, n)| format!("{}{}", sigil, n))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
 
-    // Canonical main worktree path (standard mode) for the [main] marker.
-    let main_canon = ctx.main_worktree().and_then(|m| m.canonicalize().ok());
+/// `+ahead/-behind`, or `-` when there is nothing to compare against.
+pub fn format_ahead_behind(ahead: Option<usize>, behind: Option<usize>) -> String {
+    match (ahead, behind) {
+        (Some(a), Some(b)) => format!("+{}/-{}", a, b),
+        _ => "-".to_string(),
+    }
+}
 
-    for wt in worktrees {
-        // Skip the bare repo entry (it carries the "(bare)" head marker).
-        if wt.head == "(bare)" {
-            continue;
+/// Pad every column but the last to its widest cell, joined by two spaces.
+pub fn align_columns(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .filter_map(|r| r.get(c))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (c, cell) in row.iter().enumerate() {
+                if c + 1 == row.len() {
+                    line.push_str(cell);
+                } else {
+                    line.push_str(&format!("{:<width$}  ", cell, width = widths[c]));
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Render the `--status` table. The current worktree is marked with `*`.
+pub fn format_status_table(entries: &[ListEntry], now: u64) -> Vec<String> {
+    let mut rows = vec![[
+        "", "NAME", "BRANCH", "CHANGES", "UPSTREAM", "DEFAULT", "AGE", "LOCK", "SUBJECT",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>()];
+
+    for entry in entries {
+        let marker = if entry.is_current { "*" } else { "" };
+        let branch = format_branch_info(entry.branch.as_deref(), &entry.head);
+        let lock = if entry.locked { "locked" } else { "" };
+        let row = match &entry.status {
+            Some(st) => {
+                let upstream = if st.upstream_gone {
+                    "gone".to_string()
+                } else {
+                    format_ahead_behind(st.ahead, st.behind)
+                };
+                let age = st
+                    .last_commit_time
+                    .map(|t| format_age(now.saturating_sub(t)))
+                    .unwrap_or_else(|| "-".to_string());
+                vec![
+                    marker.to_string(),
+                    entry.name.clone(),
+                    branch,
+                    format_changes(st),
+                    upstream,
+                    format_ahead_behind(st.default_ahead, st.default_behind),
+                    age,
+                    lock.to_string(),
+                    st.last_commit_subject.clone().unwrap_or_default(),
+                ]
+            }
+            None => vec![
+                marker.to_string(),
+                entry.name.clone(),
+                branch,
+                "unavailable".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                lock.to_string(),
+                String::new(),
+            ],
+        };
+        rows.push(row);
+    }
+
+    align_columns(&rows)
+}
+
+/// Render entries as porcelain: one block per worktree, blank-line separated.
+/// Every block starts with `worktree <path>`; `branch` is `detached` for a
+/// detached HEAD; flag lines (`main`, `default`, `current`, `locked`,
+/// `prunable`) appear only when set, with an optional reason after the keyword.
+/// With `--status`, `staged`, `modified`, `untracked` and `conflicted` counts
+/// follow, then `upstream`, `upstream-gone`, `ahead`, `behind`,
+/// `default-ahead`, `default-behind`, `commit-time` and `subject` when known.
+pub fn format_porcelain(entries: &[ListEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&format!("worktree {}\n", entry.path.display()));
+        out.push_str(&format!("name {}\n", entry.name));
+        out.push_str(&format!("HEAD {}\n", entry.head));
+        out.push_str(&format!(
+            "branch {}\n",
+            entry.branch.as_deref().unwrap_or("detached")
+        ));
+        for (set, flag) in [
+            (entry.is_main, "main"),
+            (entry.is_default, "default"),
+            (entry.is_current, "current"),
+        ] {
+            if set {
+                out.push_str(flag);
+                out.push('\n');
+            }
+        }
+        for (set, flag, reason) in [
+            (entry.locked, "locked", &entry.lock_reason),
+            (entry.prunable, "prunable", &entry.prunable_reason),
+        ] {
+            if set {
+                match reason {
+                    Some(r) => out.push_str(&format!("{} {}\n", flag, r)),
+                    None => out.push_str(&format!("{}\n", flag)),
+                }
+            }
+        }
+        if let Some(st) = &entry.status {
+            out.push_str(&format_porcelain_status(st));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn format_porcelain_status(st: &WorktreeStatus) -> String {
+    let mut out = String::new();
+    for (key, n) in [
+        ("staged", st.staged),
+        ("modified", st.modified),
+        ("untracked", st.untracked),
+        ("conflicted", st.conflicted),
+    ] {
+        out.push_str(&format!("{} {}\n", key, n));
+    }
+    if let Some(upstream) = &st.upstream {
+        out.push_str(&format!("upstream {}\n", upstream));
+    }
+    if st.upstream_gone {
+        out.push_str("upstream-gone\n");
+    }
+    for (key, n) in [
+        ("ahead", st.ahead),
+        ("behind", st.behind),
+        ("default-ahead", st.default_ahead),
+        ("default-behind", st.default_behind),
+    ] {
+        if let Some(n) = n {
+            out.push_str(&format!("{} {}\n", key, n));
+        }
+    }
+    if let Some(time) = st.last_commit_time {
+        out.push_str(&format!("commit-time {}\n", time));
+    }
+    if let Some(subject) = &st.last_commit_subject {
+        out.push_str(&format!("subject {}\n", subject));
+    }
+    out
+}
+
+fn load_entries(ctx: &RepoContext) -> Result<Vec<ListEntry>, Box<dyn std::error::Error>> {
+    let worktrees = get_worktree_list(ctx.anchor_dir())?;
+    let default_branch = get_repo_default_branch(ctx);
+    let current_dir = std::env::current_dir()
+        .ok()
+        .map(|d| d.canonicalize().unwrap_or(d));
+    Ok(collect_entries(
+        &worktrees,
+        ctx.main_worktree(),
+        default_branch.as_deref(),
+        current_dir.as_deref(),
+    ))
+}
+
+/// List worktrees. `template` is an explicit `--format`; without one, plain
+/// text output (not `--status`) falls back to the `list_format` config default.
+pub fn run(
+    format: ListFormat,
+    status: bool,
+    template: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let mut entries = load_entries(&ctx)?;
+
+    let template = match (format, template) {
+        (ListFormat::Text, Some(t)) => Some(t.to_string()),
+        (ListFormat::Text, None) if !status => load_config(&ctx).list_format,
+        _ => None,
+    };
+    let segments = template.as_deref().map(parse_template).transpose()?;
+
+    if status || segments.as_deref().is_some_and(needs_status) {
+        attach_status(&ctx, &mut entries);
+    }
+
+    match format {
+        ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+        ListFormat::Porcelain => {
+            print!("{}", format_porcelain(&entries));
+            return Ok(());
         }
+        ListFormat::Text => {}
+    }
 
-        let name = wt
-            .path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| wt.path.display().to_string());
+    if entries.is_empty() {
+        println!("No worktrees found.");
+        return Ok(());
+    }
 
-        let branch_info = format_branch_info(wt.branch.as_deref(), &wt.head);
+    if let Some(segments) = segments {
+        for line in render(&segments, &entries, now_timestamp(), color_enabled()) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
 
-        let is_main = main_canon
-            .as_deref()
-            .zip(wt.path.canonicalize().ok())
-            .map(|(m, p)| m == p)
-            .unwrap_or(false);
+    if status {
+        for line in format_status_table(&entries, now_timestamp()) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
 
-        if is_main {
-            println!("{:<20} [{}] (main)", name, branch_info);
+    for entry in entries {
+        let branch_info = format_branch_info(entry.branch.as_deref(), &entry.head);
+        if entry.is_main {
+            println!("{:<20} [{}] (main)", entry.name, branch_info);
         } else {
-            println!("{:<20} [{}]", name, branch_info);
+            println!("{:<20} [{}]", entry.name, branch_info);
         }
     }
 
@@ -107,4 +450,191 @@ mod tests {
         let result = format_branch_info(None, "(bare)");
         assert_eq!(result, "(bare)");
     }
+
+    fn make_worktree(path: &str, head: &str, branch: Option<&str>) -> Worktree {
+        Worktree {
+            path: PathBuf::from(path),
+            head: head.to_string(),
+            branch: branch.map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn sample_entries() -> Vec<ListEntry> {
+        let mut locked = make_worktree("/hub/feat", "def456", Some("refs/heads/feat"));
+        locked.locked = Some("usb".to_string());
+        let mut gone = make_worktree("/hub/gone", "789abc", None);
+        gone.prunable = Some(String::new());
+        let worktrees = vec![
+            make_worktree("/hub/.bare", "(bare)", None),
+            make_worktree("/hub/main", "abc123", Some("refs/heads/main")),
+            locked,
+            gone,
+        ];
+        collect_entries(
+            &worktrees,
+            None,
+            Some("main"),
+            Some(Path::new("/hub/feat/src")),
+        )
+    }
+
+    #[test]
+    fn test_collect_entries_flags() {
+        let entries = sample_entries();
+        assert_eq!(entries.len(), 3, "bare entry is skipped");
+
+        assert_eq!(entries[0].name, "main");
+        assert_eq!(entries[0].branch.as_deref(), Some("main"));
+        assert!(entries[0].is_default);
+        assert!(!entries[0].is_main);
+        assert!(!entries[0].is_current);
+
+        assert!(entries[1].is_current);
+        assert!(entries[1].locked);
+        assert_eq!(entries[1].lock_reason.as_deref(), Some("usb"));
+
+        assert!(entries[2].branch.is_none());
+        assert!(entries[2].prunable);
+        assert!(entries[2].prunable_reason.is_none());
+    }
+
+    #[test]
+    fn test_collect_entries_standard_main() {
+        let worktrees = vec![
+            make_worktree("/project", "abc123", Some("refs/heads/dev")),
+            make_worktree(
+                "/project/.claude/worktrees/x",
+                "def456",
+                Some("refs/heads/x"),
+            ),
+        ];
+        let entries = collect_entries(&worktrees, Some(Path::new("/project")), None, None);
+        assert!(entries[0].is_main);
+        assert!(!entries[1].is_main);
+        assert!(!entries[0].is_default);
+    }
+
+    #[test]
+    fn test_json_schema_fields() {
+        let entries = sample_entries();
+        let value = serde_json::to_value(&entries).unwrap();
+        let first = &value[0];
+        for key in [
+            "name",
+            "path",
+            "branch",
+            "head",
+            "is_main",
+            "is_default",
+            "is_current",
+            "locked",
+            "lock_reason",
+            "prunable",
+            "prunable_reason",
+        ] {
+            assert!(first.get(key).is_some(), "missing key {}", key);
+        }
+        assert_eq!(first["path"], "/hub/main");
+        assert_eq!(value[2]["branch"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_format_porcelain() {
+        let entries = sample_entries();
+        assert_eq!(
+            format_porcelain(&entries),
+            "\
+worktree /hub/main
+name main
+HEAD abc123
+branch main
+default
+
+worktree /hub/feat
+name feat
+HEAD def456
+branch feat
+current
+locked usb
+
+worktree /hub/gone
+name gone
+HEAD 789abc
+branch detached
+prunable
+
+"
+        );
+    }
+
+    #[test]
+    fn test_format_changes() {
+        assert_eq!(format_changes(&WorktreeStatus::default()), "clean");
+        let st = WorktreeStatus {
+            staged: 1,
+            untracked: 3,
+            ..Default::default()
+        };
+        assert_eq!(format_changes(&st), "+1 ?3");
+    }
+
+    #[test]
+    fn test_format_ahead_behind() {
+        assert_eq!(format_ahead_behind(Some(2), Some(0)), "+2/-0");
+        assert_eq!(format_ahead_behind(None, None), "-");
+    }
+
+    #[test]
+    fn test_align_columns() {
+        let rows = vec![
+            vec!["a".to_string(), "bb".to_string(), "last".to_string()],
+            vec!["ccc".to_string(), "d".to_string(), "x".to_string()],
+        ];
+        assert_eq!(align_columns(&rows), vec!["a    bb  last", "ccc  d   x"]);
+    }
+
+    #[test]
+    fn test_format_status_table_marks_current() {
+        let mut entries = sample_entries();
+        entries[1].status = Some(WorktreeStatus {
+            modified: 2,
+            upstream: Some("origin/feat".to_string()),
+            ahead: Some(1),
+            behind: Some(0),
+            default_ahead: Some(3),
+            default_behind: Some(1),
+            last_commit_time: Some(1_000),
+            last_commit_subject: Some("Add feature".to_string()),
+            ..Default::default()
+        });
+        let lines = format_status_table(&entries, 1_000 + 7_200);
+        assert!(lines[0].contains("NAME"));
+        let feat = &lines[2];
+        assert!(feat.starts_with("* "));
+        for part in [
+            "feat",
+            "~2",
+            "+1/-0",
+            "+3/-1",
+            "2h ago",
+            "locked",
+            "Add feature",
+        ] {
+            assert!(feat.contains(part), "missing {:?} in {:?}", part, feat);
+        }
+        assert!(lines[1].contains("unavailable"));
+        assert!(!lines[1].starts_with('*'));
+    }
+
+    #[test]
+    fn test_json_status_only_when_collected() {
+        let mut entries = sample_entries();
+        let value = serde_json::to_value(&entries).unwrap();
+        assert!(value[0].get("status").is_none());
+
+        entries[0].status = Some(WorktreeStatus::default());
+        let value = serde_json::to_value(&entries).unwrap();
+        assert_eq!(value[0]["status"]["staged"], 0);
+    }
 }
@@ -1,8 +1,18 @@
+pub mod checkout;
 pub mod clone;
+pub mod completions;
+pub mod config;
 pub mod create;
+pub mod du;
+pub mod env;
+pub mod gc;
+pub mod history;
 pub mod init;
 pub mod list;
+pub mod prompt;
 pub mod prune;
 pub mod remove;
+pub mod restore;
 pub mod shell_init;
 pub mod switch;
+pub mod trash;
@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+
+use crate::git::run_git_in_dir;
+
+/// What `wt prompt` shows: the hub (repository) name and, inside a worktree,
+/// which one. The main worktree of a standard repo is shown by its branch,
+/// since its directory name is the repository name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSegment {
+    pub hub: String,
+    pub worktree: Option<String>,
+}
+
+impl PromptSegment {
+    /// `hub:worktree`, or just `hub` at the hub root; `*` marks a dirty worktree.
+    pub fn render(&self, dirty: bool) -> String {
+        let mut out = self.hub.clone();
+        if let Some(worktree) = &self.worktree {
+            out.push(':');
+            out.push_str(worktree);
+        }
+        if dirty {
+            out.push('*');
+        }
+        out
+    }
+}
+
+fn dir_name(path: &Path) -> Option<String> {
+    path.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+/// Resolve a `gitdir: <path>` style pointer file relative to `base`.
+fn read_pointer(file: &Path, base: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(file).ok()?;
+    let target = content.strip_prefix("gitdir: ").unwrap_or(&content).trim();
+    if target.is_empty() {
+        return None;
+    }
+    let path = Path::new(target);
+    Some(if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    })
+}
+
+/// The branch `HEAD` points at in `gitdir`, or a short SHA when detached.
+fn head_label(gitdir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(gitdir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(r) => Some(r.strip_prefix("refs/heads/").unwrap_or(r).to_string()),
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
+/// Work out the prompt segment for `start` from `.git` files and directories
+/// alone, without running git. Also returns the worktree root, when inside one.
+pub fn locate(start: &Path) -> Option<(PromptSegment, Option<PathBuf>)> {
+    for dir in start.ancestors() {
+        let git = dir.join(".git");
+        if git.is_dir() {
+            // Main worktree of a standard repo
+            let segment = PromptSegment {
+                hub: dir_name(dir)?,
+                worktree: head_label(&git),
+            };
+            return Some((segment, Some(dir.to_path_buf())));
+        }
+        if git.is_file() {
+            let gitdir = read_pointer(&git, dir)?;
+            if gitdir.file_name().is_some_and(|n| n == ".bare") {
+                // Hub root: `.git` points straight at the bare repo
+                let hub = gitdir.parent().and_then(dir_name)?;
+                return Some((
+                    PromptSegment {
+                        hub,
+                        worktree: None,
+                    },
+                    None,
+                ));
+            }
+            // Linked worktree: `<common>/worktrees/<id>`, with `commondir` naming
+            // the shared repository (`.bare` in a hub, `.git` in a standard repo)
+            let common = read_pointer(&gitdir.join("commondir"), &gitdir)
+                .or_else(|| gitdir.parent()?.parent().map(Path::to_path_buf))?;
+            let common = common.canonicalize().unwrap_or(common);
+            let hub_dir = match common.file_name() {
+                Some(n) if n == ".bare" || n == ".git" => common.parent()?.to_path_buf(),
+                _ => common,
+            };
+            let hub = dir_name(&hub_dir)?;
+            let segment = PromptSegment {
+                hub: hub.strip_suffix(".git").unwrap_or(&hub).to_string(),
+                worktree: dir_name(dir),
+            };
+            return Some((segment, Some(dir.to_path_buf())));
+        }
+        if dir.join(".bare").is_dir() {
+            // Somewhere in a hub outside any worktree
+            let segment = PromptSegment {
+                hub: dir_name(dir)?,
+                worktree: None,
+            };
+            return Some((segment, None));
+        }
+    }
+    None
+}
+
+/// Print the prompt segment for the current directory, or nothing outside a
+/// repository. Only `dirty` spawns git (one `git status`).
+pub fn run(dirty: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(current_dir) = std::env::current_dir() else {
+        return Ok(());
+    };
+    let Some((segment, root)) = locate(&current_dir) else {
+        return Ok(());
+    };
+    let is_dirty = dirty
+        && root
+            .and_then(|r| run_git_in_dir(&r, &["status", "--porcelain"]).ok())
+            .is_some_and(|out| !out.is_empty());
+    println!("{}", segment.render(is_dirty));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn segment(hub: &str, worktree: Option<&str>) -> PromptSegment {
+        PromptSegment {
+            hub: hub.to_string(),
+            worktree: worktree.map(str::to_string),
+        }
+    }
+
+    /// `<tmp>/hub` with `.bare`, a root `.git` file and a linked worktree `feat`.
+    fn bare_hub(tmp: &Path) -> PathBuf {
+        let hub = tmp.join("hub");
+        let admin = hub.join(".bare/worktrees/feat");
+        fs::create_dir_all(&admin).unwrap();
+        fs::create_dir_all(hub.join("feat/src")).unwrap();
+        fs::write(hub.join(".git"), "gitdir: ./.bare\n").unwrap();
+        fs::write(admin.join("commondir"), "../..\n").unwrap();
+        fs::write(
+            hub.join("feat/.git"),
+            format!("gitdir: {}\n", admin.display()),
+        )
+        .unwrap();
+        hub
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            segment("hub", Some("feature-x")).render(true),
+            "hub:feature-x*"
+        );
+        assert_eq!(
+            segment("hub", Some("feature-x")).render(false),
+            "hub:feature-x"
+        );
+        assert_eq!(segment("hub", None).render(false), "hub");
+    }
+
+    #[test]
+    fn test_locate_bare_hub_worktree() {
+        let tmp = TempDir::new().unwrap();
+        let hub = bare_hub(tmp.path());
+        let (seg, root) = locate(&hub.join("feat/src")).unwrap();
+        assert_eq!(seg, segment("hub", Some("feat")));
+        assert_eq!(root, Some(hub.join("feat")));
+    }
+
+    #[test]
+    fn test_locate_bare_hub_root() {
+        let tmp = TempDir::new().unwrap();
+        let hub = bare_hub(tmp.path());
+        assert_eq!(locate(&hub).unwrap(), (segment("hub", None), None));
+    }
+
+    #[test]
+    fn test_locate_standard_layout() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().join("repo");
+        let admin = main.join(".git/worktrees/x");
+        fs::create_dir_all(&admin).unwrap();
+        fs::write(main.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(admin.join("commondir"), "../..\n").unwrap();
+        let linked = main.join(".claude/worktrees/x");
+        fs::create_dir_all(&linked).unwrap();
+        fs::write(
+            linked.join(".git"),
+            format!("gitdir: {}\n", admin.display()),
+        )
+        .unwrap();
+
+        let (seg, _) = locate(&main).unwrap();
+        assert_eq!(seg, segment("repo", Some("main")));
+        let (seg, root) = locate(&linked).unwrap();
+        assert_eq!(seg, segment("repo", Some("x")));
+        assert_eq!(root, Some(linked));
+    }
+
+    #[test]
+    fn test_locate_detached_main_worktree() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().join("repo");
+        fs::create_dir_all(main.join(".git")).unwrap();
+        fs::write(main.join(".git/HEAD"), "0123456789abcdef\n").unwrap();
+        let (seg, _) = locate(&main).unwrap();
+        assert_eq!(seg, segment("repo", Some("0123456")));
+    }
+
+    #[test]
+    fn test_locate_outside_repo() {
+        let tmp = TempDir::new().unwrap();
+        assert!(locate(tmp.path()).is_none());
+    }
+}
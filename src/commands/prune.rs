@@ -1,11 +1,18 @@
-use std::io::{self, BufRead, Write};
+use std::io;
 use std::path::{Path, PathBuf};
 
+use crate::commands::history::format_age;
+use crate::commands::remove::{expire_trash, file_in_trash, forget_removed, snapshot_for_trash};
+use crate::confirm::confirm;
+use crate::dry_run;
 use crate::git::{
-    self, detect_repo, get_current_worktree_name, get_worktree_list, run_git_in_dir, GitError,
-    Worktree,
+    self, detect_repo, get_current_worktree_name, get_worktree_list, run_git_in_dir, run_git_mut,
+    GitError, RepoContext, Worktree,
 };
-use crate::hooks::{load_hooks, run_post_hooks, run_pre_hooks, HookContext};
+use crate::hooks::{load_hooks, run_pre_hooks, try_run_post_hooks, HookContext, HooksConfig};
+use crate::parallel::{default_jobs, parallel_map};
+use crate::state::{load_state, now_timestamp, State};
+use crate::status::{collect_status, default_compare_ref};
 
 /// True when two paths point at the same location (canonicalized when possible).
 fn same_path(a: &Path, b: &Path) -> bool {
@@ -61,6 +68,182 @@ pub fn get_worktrees_to_prune(
         .collect()
 }
 
+/// Optional criteria narrowing the prune set. Every criterion given must match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneCriteria {
+    /// Branch merged into the default branch.
+    pub merged: bool,
+    /// Upstream branch deleted on the remote.
+    pub gone: bool,
+    /// No commits and no switches for at least this many seconds.
+    pub older_than: Option<u64>,
+    /// Skip worktrees with uncommitted changes.
+    pub clean_only: bool,
+}
+
+impl PruneCriteria {
+    pub fn is_empty(&self) -> bool {
+        *self == PruneCriteria::default()
+    }
+}
+
+/// What is known about a prune candidate, for matching against `PruneCriteria`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneFacts {
+    pub merged: bool,
+    pub upstream_gone: bool,
+    /// Unix time of the last commit or switch away from the worktree.
+    pub last_activity: Option<u64>,
+    pub dirty: bool,
+}
+
+/// Match `facts` against `criteria`. Returns the reasons the worktree was
+/// selected, or `None` when any criterion fails.
+pub fn match_criteria(
+    facts: &PruneFacts,
+    criteria: &PruneCriteria,
+    now: u64,
+    default_branch: &str,
+) -> Option<Vec<String>> {
+    let mut reasons = Vec::new();
+    if criteria.merged {
+        if !facts.merged {
+            return None;
+        }
+        reasons.push(format!("merged into {}", default_branch));
+    }
+    if criteria.gone {
+        if !facts.upstream_gone {
+            return None;
+        }
+        reasons.push("upstream gone".to_string());
+    }
+    if let Some(limit) = criteria.older_than {
+        let idle = now.saturating_sub(facts.last_activity?);
+        if idle < limit {
+            return None;
+        }
+        reasons.push(format!("last active {}", format_age(idle)));
+    }
+    if criteria.clean_only {
+        if facts.dirty {
+            return None;
+        }
+        reasons.push("clean".to_string());
+    }
+    Some(reasons)
+}
+
+/// Parse an age such as `30d`, `12h`, `2w` or `90m` into seconds. A bare number
+/// is days.
+pub fn parse_age(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (digits, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, "d"),
+    };
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid age '{}' (expected e.g. 30d, 12h, 2w)", s))?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(format!("invalid age unit in '{}' (use m, h, d or w)", s)),
+    };
+    n.checked_mul(unit_secs)
+        .ok_or_else(|| format!("age '{}' is too large", s))
+}
+
+/// Gather the facts `match_criteria` needs for one worktree. Commit-based facts
+/// are read from the anchor by SHA, so a worktree whose directory is gone still
+/// gets them; status-based ones default to false without a readable worktree.
+fn gather_facts(
+    anchor: &Path,
+    wt: &Worktree,
+    default_ref: Option<&str>,
+    state: &State,
+) -> PruneFacts {
+    let merged = default_ref
+        .map(|d| run_git_in_dir(anchor, &["merge-base", "--is-ancestor", &wt.head, d]).is_ok())
+        .unwrap_or(false);
+    let status = collect_status(&wt.path, None);
+    let commit_time: Option<u64> =
+        run_git_in_dir(anchor, &["show", "-s", "--format=%ct", &wt.head])
+            .ok()
+            .and_then(|t| t.trim().parse().ok());
+    let name = wt.path.file_name().map(|n| n.to_string_lossy().to_string());
+    let switched = state
+        .history
+        .iter()
+        .find(|e| Some(&e.name) == name.as_ref() && e.timestamp > 0)
+        .map(|e| e.timestamp);
+
+    PruneFacts {
+        merged,
+        upstream_gone: status.as_ref().is_some_and(|s| s.upstream_gone),
+        last_activity: commit_time.max(switched),
+        dirty: status.as_ref().is_some_and(|s| s.is_dirty()),
+    }
+}
+
+/// What happened to one prune target.
+#[derive(Debug, Default)]
+struct PruneOutcome {
+    /// Why the worktree was not removed.
+    error: Option<String>,
+    /// Problems after a successful removal (e.g. a failed post-hook).
+    warnings: Vec<String>,
+}
+
+/// Run the remove hooks, keep the worktree in the trash and remove it. Safe to
+/// run for several targets at once: nothing here touches the shared state file.
+fn remove_target(
+    ctx: &RepoContext,
+    hooks: &Option<HooksConfig>,
+    name: &str,
+    worktree_path: &Path,
+    worktree: Option<&Worktree>,
+) -> PruneOutcome {
+    let anchor = ctx.anchor_dir();
+    let context = HookContext::new("remove", name, worktree_path, anchor, None);
+    let failed = |e: String| PruneOutcome {
+        error: Some(e),
+        ..Default::default()
+    };
+
+    if let Err(e) = run_pre_hooks(hooks, &context) {
+        return failed(e.to_string());
+    }
+
+    let mut snapshot = None;
+    if let Some(wt) = worktree {
+        let branch = wt
+            .branch
+            .as_deref()
+            .map(|b| b.strip_prefix("refs/heads/").unwrap_or(b));
+        match snapshot_for_trash(ctx, wt, branch) {
+            Ok(s) => snapshot = s,
+            Err(e) => return failed(e),
+        }
+    }
+
+    let wt_path = worktree_path.to_string_lossy();
+    if let Err(e) = run_git_mut(anchor, &["worktree", "remove", wt_path.as_ref()]) {
+        return failed(e.to_string());
+    }
+
+    let mut outcome = PruneOutcome::default();
+    if let Err(e) = file_in_trash(anchor, snapshot) {
+        outcome.warnings.push(e);
+    }
+    if let Err(e) = try_run_post_hooks(hooks, &context) {
+        outcome.warnings.push(format!("post-hook failed: {}", e));
+    }
+    outcome
+}
+
 /// Format a human-readable list of worktrees that will be pruned.
 pub fn format_prune_list(names: &[String]) -> String {
     let mut lines = vec![format!(
@@ -73,13 +256,11 @@ pub fn format_prune_list(names: &[String]) -> String {
     lines.join("\n")
 }
 
-/// Parse user input for yes/no confirmation.
-/// Returns true for "y" or "yes" (case-insensitive).
-pub fn confirm_prune(input: &str) -> bool {
-    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
-}
-
-pub fn run(force: bool) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run(
+    force: bool,
+    criteria: PruneCriteria,
+    jobs: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
     let ctx = detect_repo()?;
     let anchor = ctx.anchor_dir().to_path_buf();
     let worktrees = get_worktree_list(&anchor)?;
@@ -98,7 +279,7 @@ pub fn run(force: bool) -> Result<(), Box<dyn std::error::Error>> {
     } else {
         default_branch.as_deref()
     };
-    let targets = get_worktrees_to_prune(&worktrees, default_for_filter, ctx.main_worktree());
+    let mut targets = get_worktrees_to_prune(&worktrees, default_for_filter, ctx.main_worktree());
 
     if targets.is_empty() {
         if ctx.is_standard() {
@@ -112,6 +293,40 @@ pub fn run(force: bool) -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    // Narrow the set by the given criteria, remembering why each one matched.
+    let mut reasons: Vec<Vec<String>> = vec![Vec::new(); targets.len()];
+    if !criteria.is_empty() {
+        let default_ref = default_compare_ref(&ctx);
+        if criteria.merged && default_ref.is_none() {
+            return Err(Box::new(GitError::new(
+                "Cannot determine the default branch for --merged.",
+            )));
+        }
+        let default_name = git::get_repo_default_branch(&ctx).unwrap_or_default();
+        let state = load_state(&ctx)?;
+        let now = now_timestamp();
+        let facts = parallel_map(&targets, default_jobs(), |(_, path)| {
+            worktrees
+                .iter()
+                .find(|wt| wt.path == *path)
+                .map(|wt| gather_facts(&anchor, wt, default_ref.as_deref(), &state))
+                .unwrap_or_default()
+        });
+
+        let matched: Vec<((String, PathBuf), Vec<String>)> = targets
+            .into_iter()
+            .zip(facts)
+            .filter_map(|(target, facts)| {
+                match_criteria(&facts, &criteria, now, &default_name).map(|r| (target, r))
+            })
+            .collect();
+        if matched.is_empty() {
+            println!("Nothing to prune. No worktree matches the given criteria.");
+            return Ok(());
+        }
+        (targets, reasons) = matched.into_iter().unzip();
+    }
+
     let names: Vec<String> = targets.iter().map(|(n, _)| n.clone()).collect();
 
     // Safety: refuse to prune while inside a worktree that would be removed.
@@ -129,44 +344,63 @@ pub fn run(force: bool) -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    println!("{}", format_prune_list(&names));
+    // Show why each worktree was selected next to its name.
+    let listed: Vec<String> = names
+        .iter()
+        .zip(&reasons)
+        .map(|(name, why)| {
+            if why.is_empty() {
+                name.clone()
+            } else {
+                format!("{} ({})", name, why.join(", "))
+            }
+        })
+        .collect();
+    println!("{}", format_prune_list(&listed));
 
-    if !force {
-        print!("Continue? [y/N] ");
-        io::stdout().flush()?;
+    // Nothing is removed in a dry run, so there is nothing to confirm.
+    if !force && !dry_run::is_enabled() && !confirm("Continue?")? {
+        println!("Aborted.");
+        return Ok(());
+    }
 
-        let mut input = String::new();
-        io::stdin().lock().read_line(&mut input)?;
+    let hooks = load_hooks(&ctx)?;
 
-        if !confirm_prune(&input) {
-            println!("Aborted.");
-            return Ok(());
+    // Remove up to `jobs` worktrees at once. Each removal's messages are
+    // printed as one block when it finishes, so parallel output never interleaves.
+    let outcomes = parallel_map(&targets, jobs.max(1), |(name, worktree_path)| {
+        let worktree = worktrees.iter().find(|wt| wt.path == *worktree_path);
+        let outcome = remove_target(&ctx, &hooks, name, worktree_path, worktree);
+        let _stdout = io::stdout().lock();
+        let _stderr = io::stderr().lock();
+        if outcome.error.is_none() {
+            println!(
+                "{} worktree '{}'",
+                dry_run::label("Removed", "Would remove"),
+                name
+            );
         }
-    }
+        for warning in &outcome.warnings {
+            eprintln!("Warning ({}): {}", name, warning);
+        }
+        outcome
+    });
 
-    let hooks = load_hooks(&ctx);
+    // History lives in one state file, so update it serially afterwards.
     let mut errors: Vec<(String, String)> = Vec::new();
     let mut removed = 0;
-
-    for (name, worktree_path) in &targets {
-        let context = HookContext::new("remove", name, worktree_path, &anchor, None);
-
-        if let Err(e) = run_pre_hooks(&hooks, &context) {
-            errors.push((name.clone(), e.to_string()));
-            continue;
-        }
-
-        let wt_path = worktree_path.to_string_lossy();
-        match run_git_in_dir(&anchor, &["worktree", "remove", wt_path.as_ref()]) {
-            Ok(_) => {
-                run_post_hooks(&hooks, &context);
-                println!("Removed worktree '{}'", name);
+    for ((name, worktree_path), outcome) in targets.iter().zip(outcomes) {
+        match outcome.error {
+            None => {
+                forget_removed(&ctx, worktree_path);
                 removed += 1;
             }
-            Err(e) => errors.push((name.clone(), e.to_string())),
+            Some(e) => errors.push((name.clone(), e)),
         }
     }
 
+    expire_trash(&ctx);
+
     if !errors.is_empty() {
         eprintln!("\nFailed to remove {} worktree(s):", errors.len());
         for (name, err) in &errors {
@@ -174,7 +408,11 @@ pub fn run(force: bool) -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    println!("\nPruned {} worktree(s).", removed);
+    println!(
+        "\n{} {} worktree(s).",
+        dry_run::label("Pruned", "Would prune"),
+        removed
+    );
 
     if !errors.is_empty() {
         return Err(format!("{} worktree(s) could not be removed", errors.len()).into());
@@ -192,6 +430,7 @@ mod tests {
             path: PathBuf::from(path),
             head: head.to_string(),
             branch: branch.map(|s| s.to_string()),
+            ..Default::default()
         }
     }
 
@@ -298,28 +537,89 @@ mod tests {
         );
     }
 
+    fn facts(merged: bool, gone: bool, last_activity: Option<u64>, dirty: bool) -> PruneFacts {
+        PruneFacts {
+            merged,
+            upstream_gone: gone,
+            last_activity,
+            dirty,
+        }
+    }
+
+    #[test]
+    fn test_match_criteria_empty_matches_everything() {
+        let result = match_criteria(&PruneFacts::default(), &PruneCriteria::default(), 0, "main");
+        assert_eq!(result, Some(vec![]));
+    }
+
+    #[test]
+    fn test_match_criteria_combined_reasons() {
+        let criteria = PruneCriteria {
+            merged: true,
+            gone: true,
+            older_than: Some(30 * 86_400),
+            clean_only: true,
+        };
+        let now = 100 * 86_400;
+        let result = match_criteria(&facts(true, true, Some(0), false), &criteria, now, "main");
+        assert_eq!(
+            result,
+            Some(vec![
+                "merged into main".to_string(),
+                "upstream gone".to_string(),
+                "last active 14w ago".to_string(),
+                "clean".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_match_criteria_any_failure_excludes() {
+        let criteria = PruneCriteria {
+            merged: true,
+            clean_only: true,
+            ..Default::default()
+        };
+        assert!(match_criteria(&facts(false, false, None, false), &criteria, 0, "main").is_none());
+        assert!(match_criteria(&facts(true, false, None, true), &criteria, 0, "main").is_none());
+    }
+
     #[test]
-    fn test_confirm_prune_yes() {
-        assert!(confirm_prune("y"));
-        assert!(confirm_prune("Y"));
-        assert!(confirm_prune("yes"));
-        assert!(confirm_prune("YES"));
-        assert!(confirm_prune("Yes"));
-        assert!(confirm_prune("  y  "));
-        assert!(confirm_prune("y\n"));
+    fn test_match_criteria_older_than() {
+        let criteria = PruneCriteria {
+            older_than: Some(86_400),
+            ..Default::default()
+        };
+        let recent = facts(false, false, Some(1_000), false);
+        assert!(match_criteria(&recent, &criteria, 2_000, "main").is_none());
+        let unknown = facts(false, false, None, false);
+        assert!(match_criteria(&unknown, &criteria, 2_000, "main").is_none());
+        let old = facts(false, false, Some(0), false);
+        assert!(match_criteria(&old, &criteria, 2 * 86_400, "main").is_some());
     }
 
     #[test]
-    fn test_confirm_prune_no() {
-        assert!(!confirm_prune("n"));
-        assert!(!confirm_prune("no"));
-        assert!(!confirm_prune("N"));
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d"), Ok(30 * 86_400));
+        assert_eq!(parse_age("12h"), Ok(12 * 3600));
+        assert_eq!(parse_age("2w"), Ok(2 * 604_800));
+        assert_eq!(parse_age("90m"), Ok(90 * 60));
+        assert_eq!(parse_age("7"), Ok(7 * 86_400));
+        assert!(parse_age("30x").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("").is_err());
     }
 
     #[test]
-    fn test_confirm_prune_empty() {
-        assert!(!confirm_prune(""));
-        assert!(!confirm_prune("  "));
-        assert!(!confirm_prune("\n"));
+    fn test_parse_age_overflow() {
+        assert_eq!(
+            parse_age("99999999999999999w"),
+            Err("age '99999999999999999w' is too large".to_string())
+        );
+        assert!(parse_age("99999999999999999999999d").is_err());
+        assert_eq!(
+            parse_age("30500568904943w"),
+            Ok(30_500_568_904_943 * 604_800)
+        );
     }
 }
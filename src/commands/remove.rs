@@ -1,7 +1,16 @@
 use std::path::{Path, PathBuf};
 
-use crate::git::{detect_repo, get_worktree_list, run_git_in_dir, RepoContext, Worktree};
+use crate::cd;
+use crate::dry_run;
+use crate::git::{
+    detect_repo, find_worktree_containing, get_default_branch, get_repo_default_branch,
+    get_worktree_list, run_git_in_dir, run_git_mut, RepoContext, Worktree,
+};
 use crate::hooks::{load_hooks, run_post_hooks, run_pre_hooks, HookContext};
+use crate::state::{forget_worktree, load_state, now_timestamp};
+use crate::status::{default_compare_ref, describe_work_at_risk};
+use crate::trash::{self, Snapshot};
+use crate::worktree_env;
 
 /// Format the error summary message for failed removals
 pub fn format_error_summary(error_count: usize) -> String {
@@ -21,7 +30,7 @@ fn same_path(a: &Path, b: &Path) -> bool {
     }
 }
 
-/// Resolve a worktree name to its absolute path.
+/// Resolve a worktree name to its registered worktree.
 ///
 /// Resolution is path-first: the managed location `worktree_base/<name>` is
 /// matched against the registered worktree paths, which handles nested names
@@ -29,11 +38,11 @@ fn same_path(a: &Path, b: &Path) -> bool {
 /// If no managed worktree matches, it falls back to matching the final path
 /// component; an ambiguous fallback (more than one match) is rejected rather
 /// than silently removing the first hit.
-fn resolve_worktree_path(
-    worktrees: &[Worktree],
+pub fn resolve_worktree<'a>(
+    worktrees: &'a [Worktree],
     ctx: &RepoContext,
     name: &str,
-) -> Result<PathBuf, String> {
+) -> Result<&'a Worktree, String> {
     // 1. Exact match against the managed path worktree_base/<name>.
     let candidate = ctx.worktree_base().join(name);
     let candidate_canon = candidate.canonicalize().ok();
@@ -43,7 +52,7 @@ fn resolve_worktree_path(
             _ => wt.path == candidate,
         };
         if same {
-            return Ok(wt.path.clone());
+            return Ok(wt);
         }
     }
 
@@ -60,7 +69,7 @@ fn resolve_worktree_path(
 
     match matches.len() {
         0 => Err(format!("Worktree '{}' not found", name)),
-        1 => Ok(matches[0].path.clone()),
+        1 => Ok(matches[0]),
         n => Err(format!(
             "Worktree name '{}' is ambiguous ({} worktrees share that final path component); none is at the managed path {}",
             name,
@@ -70,24 +79,234 @@ fn resolve_worktree_path(
     }
 }
 
-pub fn run(names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+/// Print a progress line. While the shell is being moved out of a removed
+/// worktree without the WT_CD_FILE handoff, stdout is reserved for the landing
+/// path, so it goes to stderr.
+fn report(message: &str, stdout_reserved: bool) {
+    if stdout_reserved {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Where to send the shell after its worktree was removed: the most recent
+/// history entry that still exists, else the default (bare) or main
+/// (standard) worktree. `None` when every candidate was removed too.
+fn choose_landing(
+    worktrees: &[Worktree],
+    history: &[String],
+    removed: &[PathBuf],
+    default_path: Option<&Path>,
+) -> Option<PathBuf> {
+    let alive = |path: &Path| !removed.iter().any(|r| same_path(r, path));
+    history
+        .iter()
+        .filter_map(|name| {
+            worktrees.iter().find(|wt| {
+                wt.head != "(bare)"
+                    && wt.path.file_name().map(|n| n.to_string_lossy()) == Some(name.into())
+            })
+        })
+        .map(|wt| wt.path.clone())
+        .chain(default_path.map(Path::to_path_buf))
+        .find(|path| alive(path))
+}
+
+/// Path of the default-branch worktree (bare) or main worktree (standard).
+fn default_worktree_path(ctx: &RepoContext, worktrees: &[Worktree]) -> Option<PathBuf> {
+    if let Some(main) = ctx.main_worktree() {
+        return Some(main.to_path_buf());
+    }
+    let branch = format!("refs/heads/{}", get_default_branch(ctx.anchor_dir())?);
+    worktrees
+        .iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch.as_str()))
+        .map(|wt| wt.path.clone())
+}
+
+/// Drop a removed worktree from the switch history and delete its stored
+/// variables, so a new worktree of the same name starts clean. Both are keyed
+/// by the worktree's directory name, which may differ from the name the user
+/// typed (e.g. `feature/foo`). A failure here only warns: the worktree is
+/// already gone.
+pub fn forget_removed(ctx: &RepoContext, worktree_path: &Path) {
+    let Some(dir_name) = worktree_path.file_name() else {
+        return;
+    };
+    let dir_name = dir_name.to_string_lossy();
+    if let Err(e) = forget_worktree(ctx, &dir_name) {
+        eprintln!("Warning: failed to update switch history: {}", e);
+    }
+    if let Err(e) = worktree_env::forget(ctx, &dir_name) {
+        eprintln!("Warning: failed to delete stored variables: {}", e);
+    }
+}
+
+/// Snapshot a worktree for the trash before removing it, keyed like the switch
+/// history by its directory name. Pass the result to `file_in_trash` once the
+/// worktree is removed.
+pub fn snapshot_for_trash(
+    ctx: &RepoContext,
+    worktree: &Worktree,
+    branch: Option<&str>,
+) -> Result<Option<Snapshot>, String> {
+    let name = worktree
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    trash::snapshot(ctx, &name, &worktree.path, &worktree.head, branch)
+        .map_err(|e| format!("could not save it to the trash: {}", e))
+}
+
+/// Add a removed worktree's snapshot to the trash.
+pub fn file_in_trash(anchor: &Path, snapshot: Option<Snapshot>) -> Result<(), String> {
+    match snapshot {
+        Some(snapshot) => snapshot
+            .file(anchor)
+            .map_err(|e| format!("removed, but could not save it to the trash: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// Expire old trash entries after removals; a failure only warns.
+pub fn expire_trash(ctx: &RepoContext) {
+    if let Err(e) = trash::expire(ctx, now_timestamp()) {
+        eprintln!("Warning: failed to expire old trash entries: {}", e);
+    }
+}
+
+/// What to do with a removed worktree's local branch under `--delete-branch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchAction {
+    Delete,
+    /// Not merged into the default branch and not forced.
+    KeepUnmerged,
+    /// The repository's default branch is never deleted.
+    KeepDefault,
+}
+
+/// Decide the `--delete-branch` action: merged branches are deleted, unmerged
+/// ones only with `--force`, and the default branch never.
+pub fn branch_action(
+    branch: &str,
+    default_branch: Option<&str>,
+    merged: bool,
+    force: bool,
+) -> BranchAction {
+    if default_branch == Some(branch) {
+        BranchAction::KeepDefault
+    } else if merged || force {
+        BranchAction::Delete
+    } else {
+        BranchAction::KeepUnmerged
+    }
+}
+
+/// Delete (or keep) the local branch of a removed worktree and report it.
+fn cleanup_branch(
+    anchor: &Path,
+    branch: &str,
+    default_branch: Option<&str>,
+    default_ref: Option<&str>,
+    force: bool,
+    stdout_reserved: bool,
+) {
+    let merged = default_ref
+        .map(|d| run_git_in_dir(anchor, &["merge-base", "--is-ancestor", branch, d]).is_ok())
+        .unwrap_or(false);
+
+    match branch_action(branch, default_branch, merged, force) {
+        BranchAction::Delete => match run_git_mut(anchor, &["branch", "-D", branch]) {
+            Ok(_) => report(
+                &format!(
+                    "{} branch '{}'",
+                    dry_run::label("Deleted", "Would delete"),
+                    branch
+                ),
+                stdout_reserved,
+            ),
+            Err(e) => eprintln!("Warning: failed to delete branch '{}': {}", branch, e),
+        },
+        BranchAction::KeepUnmerged => eprintln!(
+            "Kept branch '{}': not merged into the default branch (use --force to delete it)",
+            branch
+        ),
+        BranchAction::KeepDefault => {
+            eprintln!("Kept branch '{}': it is the default branch", branch)
+        }
+    }
+}
+
+/// Remove the named worktrees. Unless `force`, a worktree with uncommitted
+/// changes or unpushed commits is refused with a description of that work.
+/// With `delete_branch`, each removed worktree's local branch is deleted too.
+///
+/// `.` (or no name at all) is the current worktree. When the current worktree
+/// is removed, the shell is sent somewhere safe (see `choose_landing`).
+pub fn run(
+    names: &[String],
+    force: bool,
+    delete_branch: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let ctx = detect_repo()?;
     let anchor = ctx.anchor_dir().to_path_buf();
     let worktrees = get_worktree_list(&anchor)?;
-    let hooks = load_hooks(&ctx);
+    let hooks = load_hooks(&ctx)?;
+    let default_branch = get_repo_default_branch(&ctx);
+    let default_ref = default_compare_ref(&ctx);
     let mut errors: Vec<(&str, String)> = Vec::new();
 
-    for name in names {
-        // Resolve the worktree to its absolute path. Passing the bare name to
-        // `git worktree remove` only resolves in the bare layout; in standard
-        // mode the worktree lives under `.claude/worktrees/`.
-        let worktree_path = match resolve_worktree_path(&worktrees, &ctx, name) {
-            Ok(p) => p,
+    let current_dir = std::env::current_dir()
+        .ok()
+        .map(|d| d.canonicalize().unwrap_or(d));
+    let current_wt = current_dir
+        .as_deref()
+        .and_then(|d| find_worktree_containing(&worktrees, d));
+    let current_only = [".".to_string()];
+    let names = if names.is_empty() {
+        &current_only[..]
+    } else {
+        names
+    };
+
+    // Resolve the worktrees to their absolute paths up front. Passing the bare
+    // name to `git worktree remove` only resolves in the bare layout; in standard
+    // mode the worktree lives under `.claude/worktrees/`.
+    let resolved: Vec<(&str, Result<&Worktree, String>)> = names
+        .iter()
+        .map(|name| {
+            if name != "." {
+                return (name.as_str(), resolve_worktree(&worktrees, &ctx, name));
+            }
+            // Report `.` under the current worktree's directory name.
+            match current_wt {
+                Some(wt) => (
+                    wt.path.file_name().and_then(|n| n.to_str()).unwrap_or(name),
+                    Ok(wt),
+                ),
+                None => (name.as_str(), Err("not inside a worktree".to_string())),
+            }
+        })
+        .collect();
+    let is_current = |wt: &Worktree| current_wt.is_some_and(|c| same_path(&c.path, &wt.path));
+    let stdout_reserved = !dry_run::is_enabled()
+        && !cd::handoff_active()
+        && resolved
+            .iter()
+            .any(|(_, wt)| wt.as_ref().is_ok_and(|wt| is_current(wt)));
+    let mut removed: Vec<PathBuf> = Vec::new();
+
+    for (name, worktree) in resolved {
+        let worktree = match worktree {
+            Ok(wt) => wt,
             Err(msg) => {
                 errors.push((name, msg));
                 continue;
             }
         };
+        let worktree_path = worktree.path.clone();
 
         // Never remove the standard-mode main worktree.
         if let Some(main) = ctx.main_worktree() {
@@ -97,6 +316,19 @@ pub fn run(names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
+        // Refuse before any hook runs, so nothing is torn down for a removal
+        // that will not happen.
+        if !force {
+            if let Some(risk) = describe_work_at_risk(&worktree_path, default_ref.as_deref()) {
+                errors.push((name, format!("has {}; use --force to remove anyway", risk)));
+                continue;
+            }
+        }
+
+        let branch = worktree
+            .branch
+            .as_deref()
+            .map(|b| b.strip_prefix("refs/heads/").unwrap_or(b).to_string());
         let context = HookContext::new("remove", name, &worktree_path, &anchor, None);
 
         // Run pre-hooks; if they fail, skip this worktree
@@ -105,17 +337,73 @@ pub fn run(names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
             continue;
         }
 
+        let snapshot = match snapshot_for_trash(&ctx, worktree, branch.as_deref()) {
+            Ok(snapshot) => snapshot,
+            Err(e) if force => {
+                eprintln!("Warning: '{}': {}; removing anyway (--force)", name, e);
+                None
+            }
+            Err(e) => {
+                errors.push((name, format!("{}; use --force to remove anyway", e)));
+                continue;
+            }
+        };
+
         let wt_path = worktree_path.to_string_lossy();
-        match run_git_in_dir(&anchor, &["worktree", "remove", wt_path.as_ref()]) {
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(wt_path.as_ref());
+        match run_git_mut(&anchor, &args) {
             Ok(_) => {
+                if let Err(e) = file_in_trash(&anchor, snapshot) {
+                    eprintln!("Warning: '{}': {}", name, e);
+                }
                 // Run post-hooks (from hub root, worktree is gone)
                 run_post_hooks(&hooks, &context);
-                println!("Removed worktree '{}'", name);
+                forget_removed(&ctx, &worktree_path);
+                report(
+                    &format!(
+                        "{} worktree '{}'",
+                        dry_run::label("Removed", "Would remove"),
+                        name
+                    ),
+                    stdout_reserved,
+                );
+                removed.push(worktree_path.clone());
+                if delete_branch {
+                    match &branch {
+                        Some(b) => cleanup_branch(
+                            &anchor,
+                            b,
+                            default_branch.as_deref(),
+                            default_ref.as_deref(),
+                            force,
+                            stdout_reserved,
+                        ),
+                        None => eprintln!("No branch to delete: '{}' had a detached HEAD", name),
+                    }
+                }
             }
             Err(e) => errors.push((name, e.to_string())),
         }
     }
 
+    expire_trash(&ctx);
+
+    // The shell is standing in a directory that no longer exists: hand the
+    // wrapper somewhere safe to go.
+    if current_wt.is_some_and(|c| removed.iter().any(|r| same_path(r, &c.path))) {
+        let history: Vec<String> = load_state(&ctx)
+            .map(|s| s.history.into_iter().map(|e| e.name).collect())
+            .unwrap_or_default();
+        let default_path = default_worktree_path(&ctx, &worktrees);
+        let landing = choose_landing(&worktrees, &history, &removed, default_path.as_deref())
+            .unwrap_or(anchor.clone());
+        cd::request(&landing)?;
+    }
+
     if !errors.is_empty() {
         eprintln!("\nFailed to remove {} worktree(s):", errors.len());
         for (name, err) in &errors {
@@ -137,6 +425,7 @@ mod tests {
             path: PathBuf::from(path),
             head: "abc123".to_string(),
             branch: None,
+            ..Default::default()
         }
     }
 
@@ -148,6 +437,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_forget_removed_deletes_stored_variables() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let ctx = bare_ctx(tmp.path().to_str().unwrap());
+        let vars = [("PORT".to_string(), "3001".to_string())].into();
+        worktree_env::save_store(&ctx, "feat", &vars).unwrap();
+        worktree_env::merge_exports(
+            &worktree_env::hook_store_path(&ctx, Path::new("feat")),
+            "A=1",
+        )
+        .unwrap();
+        worktree_env::save_store(&ctx, "other", &vars).unwrap();
+        assert_eq!(worktree_env::stored_names(&ctx), ["feat", "other"]);
+
+        forget_removed(&ctx, &tmp.path().join("feat"));
+        assert_eq!(worktree_env::stored_names(&ctx), ["other"]);
+    }
+
     #[test]
     fn test_format_error_summary_single() {
         let result = format_error_summary(1);
@@ -183,8 +490,8 @@ mod tests {
         // Non-canonicalizable paths exercise the fallback branch.
         let worktrees = vec![make_worktree("/hub/feat"), make_worktree("/hub/other")];
         let ctx = bare_ctx("/hub");
-        let path = resolve_worktree_path(&worktrees, &ctx, "feat").unwrap();
-        assert_eq!(path, PathBuf::from("/hub/feat"));
+        let wt = resolve_worktree(&worktrees, &ctx, "feat").unwrap();
+        assert_eq!(wt.path, PathBuf::from("/hub/feat"));
     }
 
     #[test]
@@ -192,15 +499,15 @@ mod tests {
         // `feature/foo` must resolve even though its final component is `foo`.
         let worktrees = vec![make_worktree("/hub/feature/foo"), make_worktree("/hub/bar")];
         let ctx = bare_ctx("/hub");
-        let path = resolve_worktree_path(&worktrees, &ctx, "feature/foo").unwrap();
-        assert_eq!(path, PathBuf::from("/hub/feature/foo"));
+        let wt = resolve_worktree(&worktrees, &ctx, "feature/foo").unwrap();
+        assert_eq!(wt.path, PathBuf::from("/hub/feature/foo"));
     }
 
     #[test]
     fn test_resolve_missing_name() {
         let worktrees = vec![make_worktree("/hub/feat")];
         let ctx = bare_ctx("/hub");
-        let err = resolve_worktree_path(&worktrees, &ctx, "nope").unwrap_err();
+        let err = resolve_worktree(&worktrees, &ctx, "nope").unwrap_err();
         assert!(err.contains("not found"));
     }
 
@@ -213,7 +520,67 @@ mod tests {
             make_worktree("/elsewhere/b/foo"),
         ];
         let ctx = bare_ctx("/hub");
-        let err = resolve_worktree_path(&worktrees, &ctx, "foo").unwrap_err();
+        let err = resolve_worktree(&worktrees, &ctx, "foo").unwrap_err();
         assert!(err.contains("ambiguous"));
     }
+
+    #[test]
+    fn test_choose_landing_prefers_history() {
+        let worktrees = vec![
+            make_worktree("/hub/main"),
+            make_worktree("/hub/feat"),
+            make_worktree("/hub/other"),
+        ];
+        let history = vec!["gone".to_string(), "other".to_string()];
+        let landing = choose_landing(
+            &worktrees,
+            &history,
+            &[PathBuf::from("/hub/feat")],
+            Some(Path::new("/hub/main")),
+        );
+        assert_eq!(landing, Some(PathBuf::from("/hub/other")));
+    }
+
+    #[test]
+    fn test_choose_landing_skips_removed_and_falls_back_to_default() {
+        let worktrees = vec![make_worktree("/hub/main"), make_worktree("/hub/feat")];
+        let history = vec!["feat".to_string()];
+        let removed = [PathBuf::from("/hub/feat")];
+        assert_eq!(
+            choose_landing(&worktrees, &history, &removed, Some(Path::new("/hub/main"))),
+            Some(PathBuf::from("/hub/main"))
+        );
+        assert_eq!(
+            choose_landing(&worktrees, &history, &removed, Some(Path::new("/hub/feat"))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_branch_action_merged_is_deleted() {
+        assert_eq!(
+            branch_action("feat", Some("main"), true, false),
+            BranchAction::Delete
+        );
+    }
+
+    #[test]
+    fn test_branch_action_unmerged_needs_force() {
+        assert_eq!(
+            branch_action("feat", Some("main"), false, false),
+            BranchAction::KeepUnmerged
+        );
+        assert_eq!(
+            branch_action("feat", Some("main"), false, true),
+            BranchAction::Delete
+        );
+    }
+
+    #[test]
+    fn test_branch_action_never_deletes_default() {
+        assert_eq!(
+            branch_action("main", Some("main"), true, true),
+            BranchAction::KeepDefault
+        );
+    }
 }
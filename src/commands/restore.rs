@@ -0,0 +1,101 @@
+use crate::dry_run;
+use crate::git::{
+    detect_repo, get_worktree_list, local_branch_exists, run_git_in_dir, run_git_mut, GitError,
+};
+use crate::hooks::{load_hooks, run_post_hooks, run_pre_hooks, HookContext};
+use crate::trash::{forget, list_trash, restore_files};
+use crate::worktree_env;
+
+/// Recreate a removed worktree from the trash: check out its branch (or
+/// recreate the branch at the kept tip), then put back the snapshot's files.
+pub fn run(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let anchor = ctx.anchor_dir().to_path_buf();
+
+    let entry = list_trash(&anchor)?
+        .into_iter()
+        .find(|e| e.name == name)
+        .ok_or_else(|| {
+            GitError::new(format!(
+                "'{}' is not in the trash. Use 'wt trash' to see what can be restored.",
+                name
+            ))
+        })?;
+
+    if entry.path.exists() {
+        return Err(Box::new(GitError::new(format!(
+            "Cannot restore '{}': {} already exists",
+            name,
+            entry.path.display()
+        ))));
+    }
+
+    // The branch may have moved on, been checked out elsewhere, or been deleted
+    // since the removal. Only check it out when it still points at the kept tip.
+    let worktrees = get_worktree_list(&anchor)?;
+    let wt_path = entry.path.to_string_lossy();
+    let mut args = vec!["worktree", "add"];
+    let mut note = None;
+    match entry.branch.as_deref() {
+        Some(b) if !local_branch_exists(&anchor, b) => {
+            args.extend(["-b", b, wt_path.as_ref(), entry.head.as_str()]);
+            note = Some(format!("Recreated branch '{}'", b));
+        }
+        Some(b) => {
+            let tip = run_git_in_dir(&anchor, &["rev-parse", &format!("refs/heads/{}", b)])?;
+            let checked_out = worktrees
+                .iter()
+                .any(|wt| wt.branch.as_deref() == Some(format!("refs/heads/{}", b).as_str()));
+            if tip == entry.head && !checked_out {
+                args.extend([wt_path.as_ref(), b]);
+            } else {
+                args.extend(["--detach", wt_path.as_ref(), entry.head.as_str()]);
+                note = Some(format!(
+                    "Branch '{}' has moved or is checked out elsewhere; restored with a detached HEAD",
+                    b
+                ));
+            }
+        }
+        None => args.extend(["--detach", wt_path.as_ref(), entry.head.as_str()]),
+    }
+
+    let hooks = load_hooks(&ctx)?;
+    let context = HookContext::new(
+        "create",
+        name,
+        &entry.path,
+        &anchor,
+        entry.branch.as_deref(),
+    )
+    .with_exports(worktree_env::hook_store_path(&ctx, &entry.path));
+    run_pre_hooks(&hooks, &context)?;
+
+    if let Some(parent) = entry.path.parent() {
+        dry_run::create_dir_all(parent)?;
+    }
+    run_git_mut(&anchor, &args)?;
+
+    if dry_run::is_enabled() {
+        dry_run::report(&format!(
+            "restore files of {} into {}",
+            entry.commit,
+            entry.path.display()
+        ));
+    } else {
+        restore_files(&entry.path, &entry.commit)?;
+    }
+    forget(&anchor, name)?;
+
+    run_post_hooks(&hooks, &context);
+
+    if let Some(note) = note {
+        eprintln!("{}", note);
+    }
+    println!(
+        "{} worktree '{}' at {}",
+        dry_run::label("Restored", "Would restore"),
+        name,
+        entry.path.display()
+    );
+    Ok(())
+}
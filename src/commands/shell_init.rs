@@ -1,28 +1,121 @@
 use crate::git::GitError;
 
+// Every wrapper speaks the same protocol: it points `WT_CD_FILE` at a fresh
+// temp file, runs `wt` with stdout untouched, and afterwards changes into the
+// directory `wt` wrote to that file, if any (see `crate::cd`).
+//
+// bash, zsh and fish also get a hook that runs `wt env activate` whenever the
+// directory changes, by `wt` or a plain `cd`, so entering a worktree applies its
+// variables and leaving it unsets them. nu and pwsh activate after a `wt` cd only.
+
 const BASH_FUNCTION: &str = r#"wt() {
-  local output
-  output=$(command wt "$@")
+  local cd_file target exit_code
+  cd_file=$(mktemp "${TMPDIR:-/tmp}/wt-cd.XXXXXX") || return 1
+  WT_CD_FILE="$cd_file" command wt "$@"
+  exit_code=$?
+  target=$(cat "$cd_file")
+  rm -f "$cd_file"
+  if [[ -n "$target" && -d "$target" ]]; then
+    cd "$target" || return
+    __wt_env_hook
+  fi
+  return $exit_code
+}
+
+__wt_env_hook() {
   local exit_code=$?
-  if [[ $exit_code -eq 0 && ("$1" == "switch" || "$1" == "sw" || "$*" == *"--switch"* || " $* " == *" -s "* || "$*" == "-s" || "$*" == *" -s") ]]; then
-    cd "$output"
-  else
-    echo "$output"
-    return $exit_code
+  if [[ "$PWD" != "${__WT_ENV_PWD-}" ]]; then
+    __WT_ENV_PWD=$PWD
+    eval "$(command wt env activate bash 2>/dev/null)"
   fi
+  return $exit_code
 }
+
+if [[ "${PROMPT_COMMAND[*]-}" != *__wt_env_hook* ]]; then
+  PROMPT_COMMAND="__wt_env_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
 "#;
 
 const ZSH_FUNCTION: &str = r#"wt() {
-  local output
-  output=$(command wt "$@")
-  local exit_code=$?
-  if [[ $exit_code -eq 0 && ("$1" == "switch" || "$1" == "sw" || "$*" == *"--switch"* || " $* " == *" -s "* || "$*" == "-s" || "$*" == *" -s") ]]; then
-    cd "$output"
-  else
-    echo "$output"
-    return $exit_code
+  local cd_file target exit_code
+  cd_file=$(mktemp "${TMPDIR:-/tmp}/wt-cd.XXXXXX") || return 1
+  WT_CD_FILE="$cd_file" command wt "$@"
+  exit_code=$?
+  target=$(cat "$cd_file")
+  rm -f "$cd_file"
+  if [[ -n "$target" && -d "$target" ]]; then
+    cd "$target" || return
   fi
+  return $exit_code
+}
+
+__wt_env_hook() {
+  eval "$(command wt env activate zsh 2>/dev/null)"
+}
+
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd __wt_env_hook
+__wt_env_hook
+"#;
+
+const FISH_FUNCTION: &str = r#"function wt
+    set -l cd_file (mktemp)
+    or return 1
+    WT_CD_FILE=$cd_file command wt $argv
+    set -l exit_code $status
+    set -l target (cat $cd_file | string collect)
+    rm -f $cd_file
+    if test -n "$target"; and test -d "$target"
+        cd "$target"
+    end
+    return $exit_code
+end
+
+function __wt_env_hook --on-variable PWD
+    command wt env activate fish 2>/dev/null | source
+end
+__wt_env_hook
+"#;
+
+const NU_FUNCTION: &str = r#"def --env --wrapped wt [...args: string] {
+    let cd_file = (mktemp --tmpdir wt-cd.XXXXXX)
+    $env.WT_CD_FILE = $cd_file
+    try { ^wt ...$args }
+    let exit_code = $env.LAST_EXIT_CODE
+    hide-env WT_CD_FILE
+    let target = (open --raw $cd_file | str trim)
+    rm -f $cd_file
+    if ($target | is-not-empty) and (($target | path type) == "dir") {
+        cd $target
+        let plan = (try { ^wt env activate nu | from json } catch { {set: {}, unset: []} })
+        hide-env --ignore-errors ...$plan.unset
+        load-env $plan.set
+    }
+    if $exit_code != 0 {
+        error make --unspanned { msg: $"wt exited with code ($exit_code)" }
+    }
+}
+"#;
+
+const PWSH_FUNCTION: &str = r#"function wt {
+    $wtExe = (Get-Command wt -CommandType Application | Select-Object -First 1).Source
+    $cdFile = [System.IO.Path]::GetTempFileName()
+    $env:WT_CD_FILE = $cdFile
+    try {
+        & $wtExe @args
+        $exitCode = $LASTEXITCODE
+    } finally {
+        Remove-Item Env:WT_CD_FILE -ErrorAction SilentlyContinue
+    }
+    $target = Get-Content -LiteralPath $cdFile -Raw -ErrorAction SilentlyContinue
+    Remove-Item -LiteralPath $cdFile -ErrorAction SilentlyContinue
+    if ($target) { $target = $target.Trim() }
+    if ($target -and (Test-Path -LiteralPath $target -PathType Container)) {
+        Set-Location -LiteralPath $target
+        $envScript = & $wtExe env activate pwsh 2>$null | Out-String
+        if ($envScript.Trim()) { Invoke-Expression $envScript }
+    }
+    $global:LASTEXITCODE = $exitCode
 }
 "#;
 
@@ -31,8 +124,11 @@ pub fn get_shell_function(shell: &str) -> Result<&'static str, GitError> {
     match shell.to_lowercase().as_str() {
         "bash" => Ok(BASH_FUNCTION),
         "zsh" => Ok(ZSH_FUNCTION),
+        "fish" => Ok(FISH_FUNCTION),
+        "nu" | "nushell" => Ok(NU_FUNCTION),
+        "pwsh" | "powershell" => Ok(PWSH_FUNCTION),
         _ => Err(GitError::new(format!(
-            "Unsupported shell: {}. Supported shells: bash, zsh",
+            "Unsupported shell: {}. Supported shells: bash, zsh, fish, nu, pwsh",
             shell
         ))),
     }
@@ -47,6 +143,237 @@ pub fn run(shell: &str) -> Result<(), Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    // The wrapper tests run real shells against a stub `wt` script
+    #[cfg(unix)]
+    use {
+        std::fs, std::os::unix::fs::PermissionsExt, std::path::Path, std::process::Command,
+        tempfile::TempDir,
+    };
+
+    /// Command line that sources `script` in `shell`, runs `wt <args>` and
+    /// prints the working directory.
+    #[cfg(unix)]
+    fn wrapper_command(shell: &str, script: &Path, args: &str) -> Vec<String> {
+        let script = script.display();
+        let (program, flags, body) = match shell {
+            "bash" => (
+                "bash",
+                vec!["--noprofile", "--norc", "-c"],
+                format!("eval \"$(cat '{}')\"; wt {}; pwd", script, args),
+            ),
+            "zsh" => (
+                "zsh",
+                vec!["-f", "-c"],
+                format!("eval \"$(cat '{}')\"; wt {}; pwd", script, args),
+            ),
+            "fish" => (
+                "fish",
+                vec!["--no-config", "-c"],
+                format!("source '{}'; wt {}; pwd", script, args),
+            ),
+            "nu" => (
+                "nu",
+                vec!["--no-config-file", "-c"],
+                format!("source '{}'; wt {}; pwd", script, args),
+            ),
+            "pwsh" => (
+                "pwsh",
+                vec!["-NoProfile", "-NonInteractive", "-Command"],
+                format!(". '{}'; wt {}; (Get-Location).Path", script, args),
+            ),
+            _ => unreachable!(),
+        };
+        std::iter::once(program.to_string())
+            .chain(flags.into_iter().map(String::from))
+            .chain(std::iter::once(body))
+            .collect()
+    }
+
+    /// Run the emitted wrapper for `shell` against a stub `wt` that prints a line,
+    /// for `switch` requests a cd into `stub_output`, for `env` prints `activate`
+    /// and for `fail` exits 3, starting in `start`. Returns the stdout lines (the
+    /// last one is the final directory), or `None` after printing a skip notice
+    /// when the interpreter is not installed.
+    #[cfg(unix)]
+    fn run_wrapper_with(
+        shell: &str,
+        args: &str,
+        stub_output: &Path,
+        activate: &str,
+        start: &Path,
+    ) -> Option<Vec<String>> {
+        let tmp = TempDir::new().unwrap();
+        let stub = tmp.path().join("wt");
+        fs::write(
+            &stub,
+            "#!/bin/sh\n[ \"$1\" = env ] && { printf '%s\\n' \"$WT_TEST_ACTIVATE\"; exit 0; }\n[ \"$1\" = fail ] && exit 3\necho output\n[ \"$1\" = switch ] && printf '%s' \"$WT_TEST_OUTPUT\" > \"$WT_CD_FILE\"\nexit 0\n",
+        )
+        .unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+        let script = tmp.path().join("wt-init");
+        fs::write(&script, get_shell_function(shell).unwrap()).unwrap();
+
+        let argv = wrapper_command(shell, &script, args);
+        let path = format!(
+            "{}:{}",
+            tmp.path().display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let output = match Command::new(&argv[0])
+            .args(&argv[1..])
+            .current_dir(start)
+            .env("PATH", path)
+            .env("WT_TEST_OUTPUT", stub_output)
+            .env("WT_TEST_ACTIVATE", activate)
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!(
+                    "skipping the {} wrapper: cannot run {}: {}",
+                    shell, argv[0], e
+                );
+                return None;
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Some(stdout.lines().map(|l| l.trim().to_string()).collect())
+    }
+
+    /// The final directory after running the wrapper (see `run_wrapper_with`).
+    #[cfg(unix)]
+    fn run_wrapper(shell: &str, args: &str, stub_output: &Path, start: &Path) -> Option<String> {
+        let lines = run_wrapper_with(shell, args, stub_output, "", start)?;
+        Some(lines.last().cloned().unwrap_or_default())
+    }
+
+    /// Every supported shell whose interpreter is installed must cd on
+    /// `switch`, and must not cd for other commands.
+    #[cfg(unix)]
+    #[test]
+    fn test_wrappers_change_directory_when_interpreter_installed() {
+        let target = TempDir::new().unwrap();
+        let start = TempDir::new().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let start_path = start.path().canonicalize().unwrap();
+
+        for shell in ["bash", "zsh", "fish", "nu", "pwsh"] {
+            let Some(landed) = run_wrapper(shell, "switch feat", &target_path, &start_path) else {
+                continue;
+            };
+            assert_eq!(landed, target_path.display().to_string(), "{}", shell);
+
+            let stayed = run_wrapper(shell, "list", &target_path, &start_path).unwrap();
+            assert_eq!(stayed, start_path.display().to_string(), "{}", shell);
+        }
+    }
+
+    /// After a cd, the POSIX wrappers evaluate what `wt env activate` prints.
+    #[cfg(unix)]
+    #[test]
+    fn test_wrappers_apply_env_activation_after_cd() {
+        let target = TempDir::new().unwrap();
+        let start = TempDir::new().unwrap();
+        let args = "switch feat; echo \"active=$WT_TEST_ACTIVE\"";
+        for shell in ["bash", "zsh"] {
+            let Some(lines) = run_wrapper_with(
+                shell,
+                args,
+                target.path(),
+                "export WT_TEST_ACTIVE='yes'",
+                start.path(),
+            ) else {
+                continue;
+            };
+            assert!(lines.iter().any(|l| l == "active=yes"), "{}", shell);
+        }
+    }
+
+    /// A plain `cd` activates too: bash on the next prompt, zsh and fish right
+    /// away. `wt --version` stands in for a command that does not cd.
+    #[cfg(unix)]
+    #[test]
+    fn test_plain_cd_triggers_env_activation() {
+        let target = TempDir::new().unwrap();
+        let start = TempDir::new().unwrap();
+        let cd = format!("--version; cd '{}'", target.path().display());
+        for (shell, args) in [
+            (
+                "bash",
+                format!(
+                    "{}; eval \"$PROMPT_COMMAND\"; echo \"active=$WT_TEST_ACTIVE\"",
+                    cd
+                ),
+            ),
+            ("zsh", format!("{}; echo \"active=$WT_TEST_ACTIVE\"", cd)),
+            ("fish", format!("{}; echo \"active=$WT_TEST_ACTIVE\"", cd)),
+        ] {
+            let activate = if shell == "fish" {
+                "set -gx WT_TEST_ACTIVE yes"
+            } else {
+                "export WT_TEST_ACTIVE='yes'"
+            };
+            let Some(lines) = run_wrapper_with(shell, &args, target.path(), activate, start.path())
+            else {
+                continue;
+            };
+            assert!(
+                lines.iter().any(|l| l == "active=yes"),
+                "{}: {:?}",
+                shell,
+                lines
+            );
+        }
+    }
+
+    /// `wt`'s exit status reaches the caller of every wrapper. `wt --version`
+    /// is a no-op so the check can follow in each shell's own syntax.
+    #[cfg(unix)]
+    #[test]
+    fn test_wrappers_pass_on_failure() {
+        let start = TempDir::new().unwrap();
+        for (shell, check) in [
+            ("bash", "wt fail; echo \"status=$?\""),
+            ("zsh", "wt fail; echo \"status=$?\""),
+            ("fish", "wt fail; echo \"status=$status\""),
+            (
+                "nu",
+                "try { wt fail; print status=0 } catch { print status=3 }",
+            ),
+            ("pwsh", "wt fail; echo \"status=$LASTEXITCODE\""),
+        ] {
+            let args = format!("--version; {}", check);
+            let Some(lines) = run_wrapper_with(shell, &args, start.path(), "", start.path()) else {
+                continue;
+            };
+            assert!(
+                lines.iter().any(|l| l == "status=3"),
+                "{}: {:?}",
+                shell,
+                lines
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bash_prompt_hook_installed_once() {
+        let func = get_shell_function("bash").unwrap();
+        let Ok(output) = Command::new("bash")
+            .args(["--noprofile", "--norc", "-c"])
+            .arg("PROMPT_COMMAND='echo mine'; eval \"$1\"; eval \"$1\"; printf '%s' \"$PROMPT_COMMAND\"")
+            .arg("bash")
+            .arg(func)
+            .output()
+        else {
+            eprintln!("skipping: bash is not installed");
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "__wt_env_hook;echo mine"
+        );
+    }
 
     #[test]
     fn test_get_shell_function_bash() {
@@ -62,17 +389,35 @@ mod tests {
         assert!(result.unwrap().contains("wt()"));
     }
 
+    #[test]
+    fn test_get_shell_function_fish() {
+        let result = get_shell_function("fish");
+        assert!(result.is_ok());
+        assert!(result.unwrap().contains("function wt"));
+    }
+
+    #[test]
+    fn test_get_shell_function_nu_and_pwsh() {
+        assert!(get_shell_function("nu").unwrap().contains("def --env"));
+        assert!(get_shell_function("nushell").unwrap().contains("def --env"));
+        assert!(get_shell_function("pwsh").unwrap().contains("function wt"));
+        assert!(get_shell_function("powershell")
+            .unwrap()
+            .contains("Set-Location"));
+    }
+
     #[test]
     fn test_get_shell_function_case_insensitive() {
         assert!(get_shell_function("BASH").is_ok());
         assert!(get_shell_function("Bash").is_ok());
         assert!(get_shell_function("ZSH").is_ok());
         assert!(get_shell_function("Zsh").is_ok());
+        assert!(get_shell_function("FISH").is_ok());
     }
 
     #[test]
     fn test_get_shell_function_unsupported() {
-        let result = get_shell_function("fish");
+        let result = get_shell_function("tcsh");
         assert!(result.is_err());
         assert!(result.unwrap_err().message.contains("Unsupported shell"));
     }
@@ -84,17 +429,32 @@ mod tests {
     }
 
     #[test]
-    fn test_shell_function_contains_switch_detection() {
+    fn test_shell_functions_use_cd_file_protocol() {
+        for shell in ["bash", "zsh", "fish", "nu", "pwsh"] {
+            let func = get_shell_function(shell).unwrap();
+            assert!(func.contains("WT_CD_FILE"), "{}", shell);
+            assert!(func.contains("env activate"), "{}", shell);
+            // No guessing from the arguments which commands change directory
+            assert!(!func.contains("--switch"), "{}", shell);
+            assert!(!func.contains("\"sw\""), "{}", shell);
+        }
+    }
+
+    #[test]
+    fn test_shell_functions_do_not_capture_stdout() {
         let bash = get_shell_function("bash").unwrap();
         let zsh = get_shell_function("zsh").unwrap();
-
-        // Both should detect switch command and its alias
         for func in [bash, zsh] {
-            assert!(func.contains("switch"));
-            assert!(func.contains("sw"));
-            assert!(func.contains("--switch"));
-            assert!(func.contains("-s"));
-            assert!(func.contains("cd \"$output\""));
+            assert!(func.contains("WT_CD_FILE=\"$cd_file\" command wt \"$@\""));
+            assert!(!func.contains("$(command wt \"$@\""));
+            assert!(func.contains("cd \"$target\""));
         }
+        let fish = get_shell_function("fish").unwrap();
+        assert!(fish.contains("WT_CD_FILE=$cd_file command wt $argv"));
+        // The wrappers must call the binary, not recurse into themselves
+        assert!(get_shell_function("nu").unwrap().contains("^wt"));
+        assert!(get_shell_function("pwsh")
+            .unwrap()
+            .contains("-CommandType Application"));
     }
 }
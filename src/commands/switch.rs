@@ -1,48 +1,157 @@
-use crate::git::{detect_repo, get_current_worktree_name, get_worktree_list, GitError};
+use std::path::{Path, PathBuf};
+
+use crate::cd;
+use crate::commands::create;
+use crate::config::load_config;
+use crate::git::{
+    detect_repo, find_worktree_containing, get_worktree_list, local_branch_exists, GitError,
+    RepoContext, Worktree,
+};
 use crate::hooks::{load_hooks, run_post_hooks, run_pre_hooks, HookContext};
-use crate::state::{read_previous_worktree, save_previous_worktree};
+use crate::state::{read_history_entry, record_switch};
+use crate::worktree_env;
+
+/// Parse a history reference: `-` is the previous worktree (1), `-N` is the
+/// N-th most recent one. Returns `None` for anything that is a plain name.
+pub fn parse_history_ref(name: &str) -> Option<usize> {
+    if name == "-" {
+        return Some(1);
+    }
+    name.strip_prefix('-')
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|n| n.parse().ok())
+}
+
+/// Directory to land in inside `target_root`, given the subdirectory `relative`
+/// we are in within the current worktree. Walks `relative` down from the target
+/// root and stops at the deepest component that exists as a directory, so a
+/// path missing in the target falls back to its closest existing ancestor.
+pub fn resolve_landing_dir(target_root: &Path, relative: &Path) -> PathBuf {
+    let mut landing = target_root.to_path_buf();
+    for component in relative.components() {
+        let next = landing.join(component);
+        if !next.is_dir() {
+            break;
+        }
+        landing = next;
+    }
+    landing
+}
+
+/// Switch to an already-resolved worktree: run the switch hooks, record the
+/// history, and print the landing directory for the shell wrapper to cd into.
+pub fn switch_to(
+    ctx: &RepoContext,
+    worktrees: &[Worktree],
+    target: &Worktree,
+    root: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let anchor = ctx.anchor_dir();
+    let target_name = target
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| target.path.display().to_string());
+
+    // Locate the current worktree before switching (for saving state and for
+    // carrying the relative subdirectory over to the target)
+    let current_dir = std::env::current_dir()?;
+    let current_dir = current_dir.canonicalize().unwrap_or(current_dir);
+    let current_wt = find_worktree_containing(worktrees, &current_dir);
+    let current_worktree = current_wt
+        .and_then(|wt| wt.path.file_name())
+        .map(|n| n.to_string_lossy().to_string());
+    let relative = current_wt
+        .and_then(|wt| {
+            let wt_path = wt.path.canonicalize().unwrap_or_else(|_| wt.path.clone());
+            current_dir
+                .strip_prefix(&wt_path)
+                .ok()
+                .map(|p| p.to_path_buf())
+        })
+        .unwrap_or_default();
+    let keep_subdir = !root && load_config(ctx).keep_subdir.unwrap_or(true);
+
+    // Load and run pre-hooks
+    let hooks = load_hooks(ctx)?;
+    let context = HookContext::new("switch", &target_name, &target.path, anchor, None)
+        .with_exports(worktree_env::hook_store_path(ctx, &target.path));
+    run_pre_hooks(&hooks, &context)?;
 
-pub fn run(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Push the current worktree onto the history and drop the target
+    record_switch(ctx, current_worktree.as_deref(), &target_name)?;
+
+    // Run post-hooks (from target worktree)
+    run_post_hooks(&hooks, &context);
+
+    // Hand the landing directory to the shell wrapper
+    let landing = if keep_subdir {
+        resolve_landing_dir(&target.path, &relative)
+    } else {
+        target.path.clone()
+    };
+    cd::request(&landing)?;
+    Ok(())
+}
+
+/// The worktree `name` refers to: the one whose directory is called `name`, or
+/// with `create` the one that already has branch `name` checked out under some
+/// other directory name, since git would refuse to check it out a second time.
+pub fn find_target<'a>(
+    worktrees: &'a [Worktree],
+    name: &str,
+    create: bool,
+) -> Option<&'a Worktree> {
+    let by_dir = worktrees.iter().find(|wt| {
+        wt.path
+            .file_name()
+            .map(|n| n.to_string_lossy() == name)
+            .unwrap_or(false)
+    });
+    let branch = format!("refs/heads/{}", name);
+    by_dir.or_else(|| {
+        create
+            .then(|| {
+                worktrees
+                    .iter()
+                    .find(|wt| wt.branch.as_deref() == Some(branch.as_str()))
+            })
+            .flatten()
+    })
+}
+
+/// Switch to the worktree `name`. With `create`, a missing worktree is created
+/// and switched to instead (running the create hooks rather than the switch
+/// hooks); an existing local branch of that name is checked out rather than
+/// branched anew.
+pub fn run(name: &str, root: bool, create: bool) -> Result<(), Box<dyn std::error::Error>> {
     let ctx = detect_repo()?;
     let anchor = ctx.anchor_dir().to_path_buf();
 
-    // Resolve "-" to the previous worktree name
-    let target_name = if name == "-" {
-        read_previous_worktree(&ctx)?
-            .ok_or_else(|| GitError::new("No previous worktree. Use 'wt switch <name>' first."))?
-    } else {
-        name.to_string()
+    // Resolve "-" / "-N" to an entry of the switch history
+    let target_name = match parse_history_ref(name) {
+        Some(n) => read_history_entry(&ctx, n)?.ok_or_else(|| {
+            if n == 1 {
+                GitError::new("No previous worktree. Use 'wt switch <name>' first.")
+            } else {
+                GitError::new(format!(
+                    "No worktree {} entries back. Use 'wt history' to see the switch history.",
+                    n
+                ))
+            }
+        })?,
+        None => name.to_string(),
     };
 
     let worktrees = get_worktree_list(&anchor)?;
 
-    // Get current worktree name before switching (for saving state)
-    let current_worktree = get_current_worktree_name(&anchor)?;
-
-    // Find the worktree by name (matching the directory name)
-    for wt in &worktrees {
-        if let Some(dir_name) = wt.path.file_name() {
-            if dir_name.to_string_lossy() == target_name {
-                // Load and run pre-hooks
-                let hooks = load_hooks(&ctx);
-                let context = HookContext::new("switch", &target_name, &wt.path, &anchor, None);
-                run_pre_hooks(&hooks, &context)?;
-
-                // Save current worktree as previous (only if different from target)
-                if let Some(ref current) = current_worktree {
-                    if current != &target_name {
-                        save_previous_worktree(&ctx, current)?;
-                    }
-                }
-
-                // Run post-hooks (from target worktree)
-                run_post_hooks(&hooks, &context);
-
-                // Print path for shell wrapper to cd into
-                println!("{}", wt.path.display());
-                return Ok(());
-            }
-        }
+    if let Some(wt) = find_target(&worktrees, &target_name, create) {
+        return switch_to(&ctx, &worktrees, wt, root);
+    }
+
+    if create {
+        let checkout = local_branch_exists(&anchor, &target_name).then_some(target_name.as_str());
+        return create::run(&target_name, checkout, None, true);
     }
 
     Err(Box::new(GitError::new(format!(
@@ -50,3 +159,95 @@ pub fn run(name: &str) -> Result<(), Box<dyn std::error::Error>> {
         target_name
     ))))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{git, init_repo};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_target_resolves_checked_out_branch_when_creating() {
+        let repo = init_repo();
+        let other = repo.path().join("wts/other");
+        git(
+            repo.path(),
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "feature",
+                other.to_str().unwrap(),
+            ],
+        );
+        let worktrees = get_worktree_list(repo.path()).unwrap();
+
+        let found = find_target(&worktrees, "feature", true).unwrap();
+        assert_eq!(found.path.file_name().unwrap(), "other");
+        assert_eq!(
+            find_target(&worktrees, "other", false).map(|wt| &wt.path),
+            Some(&found.path)
+        );
+        // Without create, only directory names match
+        assert!(find_target(&worktrees, "feature", false).is_none());
+        assert!(find_target(&worktrees, "missing", true).is_none());
+    }
+
+    #[test]
+    fn test_resolve_landing_dir_existing_path() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("services/api/src")).unwrap();
+        let landing = resolve_landing_dir(tmp.path(), Path::new("services/api/src"));
+        assert_eq!(landing, tmp.path().join("services/api/src"));
+    }
+
+    #[test]
+    fn test_resolve_landing_dir_deepest_existing_ancestor() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("services")).unwrap();
+        let landing = resolve_landing_dir(tmp.path(), Path::new("services/api/src"));
+        assert_eq!(landing, tmp.path().join("services"));
+    }
+
+    #[test]
+    fn test_resolve_landing_dir_root() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(resolve_landing_dir(tmp.path(), Path::new("")), tmp.path());
+        assert_eq!(
+            resolve_landing_dir(tmp.path(), Path::new("missing")),
+            tmp.path()
+        );
+    }
+
+    #[test]
+    fn test_resolve_landing_dir_file_is_not_a_landing() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(tmp.path().join("README"), "").unwrap();
+        assert_eq!(
+            resolve_landing_dir(tmp.path(), Path::new("README")),
+            tmp.path()
+        );
+    }
+
+    #[test]
+    fn test_parse_history_ref_dash() {
+        assert_eq!(parse_history_ref("-"), Some(1));
+    }
+
+    #[test]
+    fn test_parse_history_ref_numbered() {
+        assert_eq!(parse_history_ref("-1"), Some(1));
+        assert_eq!(parse_history_ref("-3"), Some(3));
+        assert_eq!(parse_history_ref("-12"), Some(12));
+    }
+
+    #[test]
+    fn test_parse_history_ref_plain_names() {
+        assert_eq!(parse_history_ref("main"), None);
+        assert_eq!(parse_history_ref("feature-2"), None);
+        assert_eq!(parse_history_ref("-x"), None);
+        assert_eq!(parse_history_ref("--"), None);
+    }
+}
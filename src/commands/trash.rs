@@ -0,0 +1,42 @@
+use crate::commands::history::format_age;
+use crate::commands::list::align_columns;
+use crate::git::detect_repo;
+use crate::state::now_timestamp;
+use crate::trash::{expire, list_trash};
+
+/// List removed worktrees kept in the trash, after expiring old entries.
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = detect_repo()?;
+    let now = now_timestamp();
+    expire(&ctx, now)?;
+
+    let entries = list_trash(ctx.anchor_dir())?;
+    if entries.is_empty() {
+        println!("Trash is empty.");
+        return Ok(());
+    }
+
+    let mut rows = vec![vec![
+        "NAME".to_string(),
+        "BRANCH".to_string(),
+        "HEAD".to_string(),
+        "REMOVED".to_string(),
+        "PATH".to_string(),
+    ]];
+    for entry in &entries {
+        rows.push(vec![
+            entry.name.clone(),
+            entry.branch.clone().unwrap_or_else(|| "-".to_string()),
+            entry.head.chars().take(7).collect(),
+            format_age(now.saturating_sub(entry.timestamp)),
+            entry.path.display().to_string(),
+        ]);
+    }
+    for line in align_columns(&rows) {
+        println!("{}", line);
+    }
+    println!();
+    println!("Use 'wt restore <name>' to bring one back.");
+
+    Ok(())
+}
@@ -1,44 +1,280 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
 use serde::Deserialize;
 
-use crate::git::RepoContext;
+use crate::git::{expand_worktree_base, RepoContext};
+use crate::list_template::parse_template;
+use crate::worktree_env::is_valid_key;
 
 const CONFIG_FILE_NAME: &str = "config.toml";
 
-/// Optional per-repo configuration loaded from `<state_dir>/config.toml`.
+/// Optional configuration: per-repo in `<state_dir>/config.toml`, with defaults
+/// for every repo in the global `config.toml` (see `global_dirs`).
 #[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct WtreeConfig {
-    /// Override for where standard-layout worktrees are created. Relative paths
-    /// resolve against the main worktree, absolute paths are used as-is.
+    /// Override for where worktrees are created, in either layout, including the
+    /// default-branch worktree of `wt clone`. A directory or a `{hub}`/`{repo}`/
+    /// `{name}`/`~` template, relative to the hub root or main worktree (see
+    /// `expand_worktree_base`).
     #[serde(default)]
     pub worktree_base: Option<String>,
+    /// Whether `wt switch` lands in the same relative subdirectory of the target
+    /// worktree (or its deepest existing ancestor) instead of the worktree root.
+    /// Defaults to true; `wt switch --root` overrides it per invocation.
+    #[serde(default)]
+    pub keep_subdir: Option<bool>,
+    /// Default `wt list` template (see `wt list --format`), used for the text
+    /// output when no `--format` is given.
+    #[serde(default)]
+    pub list_format: Option<String>,
+    /// Days removed worktrees stay in the trash for `wt restore` (default 14).
+    /// `0` disables the trash.
+    #[serde(default)]
+    pub trash_days: Option<u64>,
+    /// Variables the shell integration sets in every worktree of this repo
+    /// (`[env]` table). `{name}` and `{path}` are replaced with the worktree's.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
 }
 
-/// Load config from `<state_dir>/config.toml`.
-///
-/// A missing file returns the default. A parse error warns to stderr and falls
-/// back to the default rather than aborting the command, so a malformed config
-/// never bricks `wt`.
+impl WtreeConfig {
+    /// `over` layered on top of `self`: every key set in `over` wins, and `[env]`
+    /// tables merge per variable.
+    pub fn overlay(mut self, over: WtreeConfig) -> WtreeConfig {
+        self.env.extend(over.env);
+        WtreeConfig {
+            worktree_base: over.worktree_base.or(self.worktree_base),
+            keep_subdir: over.keep_subdir.or(self.keep_subdir),
+            list_format: over.list_format.or(self.list_format),
+            trash_days: over.trash_days.or(self.trash_days),
+            env: self.env,
+        }
+    }
+}
+
+/// Directories holding global wtree files, most important first:
+/// `$XDG_CONFIG_HOME/wtree` (`~/.config/wtree` when unset), then the legacy
+/// `~/.wtree`. Empty in unit tests, which must not depend on the global files
+/// of whoever runs them.
+pub fn global_dirs() -> Vec<PathBuf> {
+    if cfg!(test) {
+        return Vec::new();
+    }
+    global_dirs_from(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+    )
+}
+
+fn global_dirs_from(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Vec<PathBuf> {
+    let home = home.filter(|h| !h.is_empty()).map(PathBuf::from);
+    // The XDG spec says to ignore a relative XDG_CONFIG_HOME
+    let xdg = xdg_config_home
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home.as_ref().map(|h| h.join(".config")));
+
+    xdg.map(|x| x.join("wtree"))
+        .into_iter()
+        .chain(home.map(|h| h.join(".wtree")))
+        .collect()
+}
+
+/// The repo's own config file.
+pub fn repo_config_path(ctx: &RepoContext) -> PathBuf {
+    ctx.state_dir().join(CONFIG_FILE_NAME)
+}
+
+/// The global config file edits go to: the XDG one.
+pub fn global_config_path() -> Option<PathBuf> {
+    global_dirs()
+        .into_iter()
+        .next()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+/// Config files in increasing precedence: the global files (legacy `~/.wtree`
+/// first, so the XDG file wins over it), then the repo's own when in a repo.
+pub fn config_layers(ctx: Option<&RepoContext>) -> Vec<PathBuf> {
+    config_layers_in(&global_dirs(), ctx)
+}
+
+/// `config_layers` with the global directories `dirs` (as from `global_dirs`).
+fn config_layers_in(dirs: &[PathBuf], ctx: Option<&RepoContext>) -> Vec<PathBuf> {
+    dirs.iter()
+        .rev()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .chain(ctx.map(repo_config_path))
+        .collect()
+}
+
+/// Load the effective config for a repo: each file of `config_layers` overlays
+/// the ones before it, so the repo's config beats the global one key by key.
 pub fn load_config(ctx: &RepoContext) -> WtreeConfig {
-    let path = ctx.state_dir().join(CONFIG_FILE_NAME);
+    load_config_with(ctx, &global_dirs())
+}
+
+/// `load_config` with the global directories `dirs` instead of `global_dirs`.
+pub fn load_config_with(ctx: &RepoContext, dirs: &[PathBuf]) -> WtreeConfig {
+    load_layers(&config_layers_in(dirs, Some(ctx)))
+}
 
-    let content = match std::fs::read_to_string(&path) {
+fn load_layers(paths: &[PathBuf]) -> WtreeConfig {
+    paths
+        .iter()
+        .map(|path| load_config_file(path))
+        .fold(WtreeConfig::default(), WtreeConfig::overlay)
+}
+
+/// Top-level keys of `config.toml`.
+const FIELDS: &[&str] = &[
+    "worktree_base",
+    "keep_subdir",
+    "list_format",
+    "trash_days",
+    "env",
+];
+
+/// Paths already warned about, so a broken file is reported once per run even
+/// though several code paths load the config.
+static WARNED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Load one config file.
+///
+/// A missing file returns the default. Problems warn to stderr (once, with the
+/// line and column) rather than aborting the command, so a malformed config
+/// never bricks `wt`: an unknown key or a value of the wrong type is skipped,
+/// and a file that is not valid TOML is ignored as a whole.
+pub fn load_config_file(path: &Path) -> WtreeConfig {
+    let content = match std::fs::read_to_string(path) {
         Ok(c) => c,
         Err(_) => return WtreeConfig::default(),
     };
 
-    match toml::from_str(&content) {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            eprintln!(
-                "Warning: failed to parse {}: {}. Using defaults.",
-                path.display(),
-                e
-            );
-            WtreeConfig::default()
+    let (cfg, problems, note) = match parse_config(path, &content) {
+        Ok((cfg, problems)) => (cfg, problems, "setting ignored"),
+        Err(e) => (WtreeConfig::default(), vec![e], "file ignored"),
+    };
+    let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+    if !problems.is_empty() && !warned.iter().any(|p| p == path) {
+        warned.push(path.to_path_buf());
+        for problem in problems {
+            eprintln!("Warning: {} ({})", problem, note);
+        }
+    }
+    cfg
+}
+
+/// Parse a config file's `content`, keeping every valid setting. Unknown keys
+/// and values of the wrong type are left out and returned as problems; a TOML
+/// syntax error is an `Err`. Both read `path:line:column: message`.
+pub fn parse_config(path: &Path, content: &str) -> Result<(WtreeConfig, Vec<String>), String> {
+    let table: toml::Table =
+        toml::from_str(content).map_err(|e| describe_toml_error(path, content, &e))?;
+    // Same syntax, parsed again for the positions of keys and values
+    let doc = toml_edit::ImDocument::parse(content).ok();
+    let locate = |span: Option<std::ops::Range<usize>>| match span {
+        Some(span) => {
+            let (line, column) = line_col(content, span.start);
+            (
+                span.start,
+                format!("{}:{}:{}", path.display(), line, column),
+            )
+        }
+        None => (0, path.display().to_string()),
+    };
+
+    let mut cfg = WtreeConfig::default();
+    let mut problems = Vec::new();
+    for (key, value) in table {
+        let entry = doc.as_ref().and_then(|d| d.as_table().get_key_value(&key));
+
+        if let ("env", toml::Value::Table(vars)) = (key.as_str(), &value) {
+            let env = entry.and_then(|(_, item)| item.as_table_like());
+            for (var, value) in vars {
+                match value.clone().try_into::<String>() {
+                    Ok(value) => {
+                        cfg.env.insert(var.clone(), value);
+                    }
+                    Err(e) => {
+                        let span = env.and_then(|t| t.get_key_value(var)?.1.span());
+                        let (at, place) = locate(span);
+                        problems.push((at, format!("{}: env.{}: {}", place, var, e.message())));
+                    }
+                }
+            }
+            continue;
+        }
+
+        // Point at the value when the key is right, at the key otherwise
+        let span = entry.and_then(|(k, item)| {
+            if FIELDS.contains(&key.as_str()) {
+                item.span()
+            } else {
+                k.span()
+            }
+        });
+        let mut single = toml::Table::new();
+        single.insert(key, value);
+        match single.try_into::<WtreeConfig>() {
+            Ok(part) => cfg = cfg.overlay(part),
+            Err(e) => {
+                let (at, place) = locate(span);
+                problems.push((at, format!("{}: {}", place, e.message())));
+            }
+        }
+    }
+    problems.sort();
+    Ok((cfg, problems.into_iter().map(|(_, p)| p).collect()))
+}
+
+/// `path:line:column: message` for a TOML or schema error in `content`.
+pub fn describe_toml_error(path: &Path, content: &str, err: &toml::de::Error) -> String {
+    match err.span() {
+        Some(span) => {
+            let (line, column) = line_col(content, span.start);
+            format!("{}:{}:{}: {}", path.display(), line, column, err.message())
         }
+        None => format!("{}: {}", path.display(), err.message()),
     }
 }
 
+/// 1-based line and column (in characters) of byte `offset` in `content`.
+fn line_col(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Problems with values that parse but cannot be used: a bad `worktree_base`
+/// template, an invalid `list_format` or `[env]` names no shell can hold.
+pub fn check_config(cfg: &WtreeConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(base) = &cfg.worktree_base {
+        // Only the template matters here; any anchor and home will do
+        let root = Path::new("/");
+        if let Err(e) = expand_worktree_base(base, root, Some(root)) {
+            problems.push(format!("worktree_base: {}", e));
+        }
+    }
+    if let Some(format) = &cfg.list_format {
+        if let Err(e) = parse_template(format) {
+            problems.push(format!("list_format: {}", e));
+        }
+    }
+    for name in cfg.env.keys().filter(|name| !is_valid_key(name)) {
+        problems.push(format!("env: '{}' is not a valid variable name", name));
+    }
+    problems
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,35 +300,148 @@ mod tests {
     #[test]
     fn test_missing_config_is_default() {
         let (_tmp, ctx) = ctx_with_config(None);
-        let cfg = load_config(&ctx);
+        let cfg = load_config_with(&ctx, &[]);
         assert!(cfg.worktree_base.is_none());
     }
 
     #[test]
     fn test_config_with_worktree_base() {
         let (_tmp, ctx) = ctx_with_config(Some("worktree_base = \"wt\"\n"));
-        let cfg = load_config(&ctx);
+        let cfg = load_config_with(&ctx, &[]);
         assert_eq!(cfg.worktree_base.as_deref(), Some("wt"));
     }
 
     #[test]
     fn test_config_absolute_worktree_base() {
         let (_tmp, ctx) = ctx_with_config(Some("worktree_base = \"/abs/elsewhere\"\n"));
-        let cfg = load_config(&ctx);
+        let cfg = load_config_with(&ctx, &[]);
         assert_eq!(cfg.worktree_base.as_deref(), Some("/abs/elsewhere"));
     }
 
     #[test]
     fn test_garbage_config_falls_back_to_default() {
         let (_tmp, ctx) = ctx_with_config(Some("this is not = valid = toml ["));
-        let cfg = load_config(&ctx);
+        let cfg = load_config_with(&ctx, &[]);
         assert!(cfg.worktree_base.is_none());
     }
 
+    #[test]
+    fn test_unknown_key_keeps_the_known_ones() {
+        let (_tmp, ctx) = ctx_with_config(Some(
+            "worktree_base = \"wt\"\ntrash_days = \"soon\"\n[env]\nA = \"1\"\n[crate]\n",
+        ));
+        let cfg = load_config_with(&ctx, &[]);
+        assert_eq!(cfg.worktree_base.as_deref(), Some("wt"));
+        assert_eq!(cfg.trash_days, None);
+        assert_eq!(cfg.env.get("A").map(String::as_str), Some("1"));
+    }
+
+    #[test]
+    fn test_parse_config_reports_each_problem() {
+        let content = "worktree_bse = \"x\"\nkeep_subdir = true\ntrash_days = \"soon\"\n\n[env]\nPORT = 3000\nB = \"b\"\n";
+        let (cfg, problems) = parse_config(Path::new("c.toml"), content).unwrap();
+        assert_eq!(cfg.keep_subdir, Some(true));
+        assert_eq!(cfg.env.len(), 1);
+        assert_eq!(
+            problems,
+            vec![
+                "c.toml:1:1: unknown field `worktree_bse`, expected one of `worktree_base`, \
+                 `keep_subdir`, `list_format`, `trash_days`, `env`",
+                "c.toml:3:14: invalid type: string \"soon\", expected u64",
+                "c.toml:6:8: env.PORT: invalid type: integer `3000`, expected a string",
+            ]
+        );
+        assert!(parse_config(Path::new("c.toml"), "a = [")
+            .unwrap_err()
+            .starts_with("c.toml:1:"));
+    }
+
+    #[test]
+    fn test_line_col() {
+        let content = "a = 1\nbé = 2\n";
+        assert_eq!(line_col(content, 0), (1, 1));
+        assert_eq!(line_col(content, 6), (2, 1));
+        assert_eq!(line_col(content, content.find('=').unwrap()), (1, 3));
+        assert_eq!(line_col(content, content.rfind('=').unwrap()), (2, 4));
+    }
+
+    #[test]
+    fn test_config_keep_subdir() {
+        let (_tmp, ctx) = ctx_with_config(Some("keep_subdir = false\n"));
+        let cfg = load_config_with(&ctx, &[]);
+        assert_eq!(cfg.keep_subdir, Some(false));
+    }
+
+    #[test]
+    fn test_global_dirs_prefer_xdg() {
+        assert_eq!(
+            global_dirs_from(Some("/xdg".into()), Some("/home/u".into())),
+            vec![PathBuf::from("/xdg/wtree"), PathBuf::from("/home/u/.wtree")]
+        );
+    }
+
+    #[test]
+    fn test_global_dirs_default_and_relative_xdg() {
+        let expected = vec![
+            PathBuf::from("/home/u/.config/wtree"),
+            PathBuf::from("/home/u/.wtree"),
+        ];
+        assert_eq!(global_dirs_from(None, Some("/home/u".into())), expected);
+        assert_eq!(
+            global_dirs_from(Some("rel".into()), Some("/home/u".into())),
+            expected
+        );
+        assert!(global_dirs_from(None, None).is_empty());
+    }
+
+    #[test]
+    fn test_layers_later_files_win_per_key() {
+        let tmp = TempDir::new().unwrap();
+        let legacy = tmp.path().join("legacy.toml");
+        let xdg = tmp.path().join("xdg.toml");
+        let repo = tmp.path().join("repo.toml");
+        fs::write(
+            &legacy,
+            "trash_days = 3\nlist_format = \"{name}\"\n[env]\nA = \"legacy\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &xdg,
+            "trash_days = 7\nkeep_subdir = false\n[env]\nB = \"xdg\"\n",
+        )
+        .unwrap();
+        fs::write(&repo, "keep_subdir = true\n[env]\nA = \"repo\"\n").unwrap();
+
+        let cfg = load_layers(&[legacy, xdg, repo, tmp.path().join("missing.toml")]);
+        assert_eq!(cfg.trash_days, Some(7));
+        assert_eq!(cfg.keep_subdir, Some(true));
+        assert_eq!(cfg.list_format.as_deref(), Some("{name}"));
+        assert_eq!(cfg.env.get("A").map(String::as_str), Some("repo"));
+        assert_eq!(cfg.env.get("B").map(String::as_str), Some("xdg"));
+    }
+
+    #[test]
+    fn test_load_config_merges_global_dirs_under_the_repo() {
+        let (tmp, ctx) = ctx_with_config(Some("keep_subdir = true\n"));
+        let home = tmp.path().join("home");
+        let dirs = global_dirs_from(None, Some(home.clone().into()));
+        fs::create_dir_all(home.join(".config/wtree")).unwrap();
+        fs::write(
+            home.join(".config/wtree/config.toml"),
+            "keep_subdir = false\ntrash_days = 3\n",
+        )
+        .unwrap();
+
+        let cfg = load_config_with(&ctx, &dirs);
+        assert_eq!(cfg.keep_subdir, Some(true));
+        assert_eq!(cfg.trash_days, Some(3));
+        assert_eq!(load_config_with(&ctx, &[]).trash_days, None);
+    }
+
     #[test]
     fn test_empty_config_is_default() {
         let (_tmp, ctx) = ctx_with_config(Some(""));
-        let cfg = load_config(&ctx);
+        let cfg = load_config_with(&ctx, &[]);
         assert!(cfg.worktree_base.is_none());
     }
 }
@@ -0,0 +1,50 @@
+use std::io::{self, BufRead, Write};
+
+/// Ask a yes/no question and read the answer from stdin. Defaults to no.
+///
+/// The question goes to stderr, not stdout: commands that change directory print
+/// the target path on stdout for the shell wrapper, which captures it.
+pub fn confirm(question: &str) -> io::Result<bool> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input)?;
+    Ok(is_yes(&input))
+}
+
+/// Parse user input for yes/no confirmation.
+/// Returns true for "y" or "yes" (case-insensitive).
+pub fn is_yes(input: &str) -> bool {
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_yes_yes() {
+        assert!(is_yes("y"));
+        assert!(is_yes("Y"));
+        assert!(is_yes("yes"));
+        assert!(is_yes("YES"));
+        assert!(is_yes("Yes"));
+        assert!(is_yes("  y  "));
+        assert!(is_yes("y\n"));
+    }
+
+    #[test]
+    fn test_is_yes_no() {
+        assert!(!is_yes("n"));
+        assert!(!is_yes("no"));
+        assert!(!is_yes("N"));
+    }
+
+    #[test]
+    fn test_is_yes_empty() {
+        assert!(!is_yes(""));
+        assert!(!is_yes("  "));
+        assert!(!is_yes("\n"));
+    }
+}
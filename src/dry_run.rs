@@ -0,0 +1,98 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Process-wide `--dry-run` switch, set once from `main` before any command runs.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Report an action that dry-run mode skipped. Goes to stderr so commands whose
+/// stdout is a path for the shell wrapper keep a clean stdout.
+pub fn report(action: &str) {
+    eprintln!("[dry-run] {}", action);
+}
+
+/// Pick the past-tense message for a real run or the conditional one for a dry run.
+pub fn label<'a>(done: &'a str, would: &'a str) -> &'a str {
+    if is_enabled() {
+        would
+    } else {
+        done
+    }
+}
+
+/// Render a command line, quoting arguments that would not survive a shell as-is.
+pub fn format_command(program: &str, args: &[&str]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().copied())
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./:=@+,%".contains(c));
+            if plain {
+                arg.to_string()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `fs::create_dir_all`, reported instead of performed in dry-run mode.
+pub fn create_dir_all(path: &Path) -> std::io::Result<()> {
+    if is_enabled() {
+        if !path.is_dir() {
+            report(&format!("mkdir -p {}", path.display()));
+        }
+        return Ok(());
+    }
+    std::fs::create_dir_all(path)
+}
+
+/// `fs::write`, reported instead of performed in dry-run mode.
+pub fn write_file(path: &Path, content: &str) -> std::io::Result<()> {
+    if is_enabled() {
+        report(&format!("write {}", path.display()));
+        return Ok(());
+    }
+    std::fs::write(path, content)
+}
+
+/// `fs::remove_dir_all`, reported instead of performed in dry-run mode.
+pub fn remove_dir_all(path: &Path) -> std::io::Result<()> {
+    if is_enabled() {
+        report(&format!("rm -r {}", path.display()));
+        return Ok(());
+    }
+    std::fs::remove_dir_all(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_command_plain_args() {
+        assert_eq!(
+            format_command("git", &["-C", "/hub", "worktree", "remove", "/hub/feat"]),
+            "git -C /hub worktree remove /hub/feat"
+        );
+    }
+
+    #[test]
+    fn test_format_command_quotes_special_args() {
+        assert_eq!(
+            format_command("sh", &["-c", "npm install && echo done"]),
+            "sh -c 'npm install && echo done'"
+        );
+        assert_eq!(format_command("echo", &["it's", ""]), r"echo 'it'\''s' ''");
+    }
+}
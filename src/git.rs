@@ -1,6 +1,8 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::dry_run;
+
 #[derive(Debug)]
 pub struct GitError {
     pub message: String,
@@ -38,6 +40,19 @@ pub fn run_git_in_dir(dir: &Path, args: &[&str]) -> Result<String, GitError> {
     }
 }
 
+/// Execute a git command that changes the repository. In dry-run mode the
+/// command is reported and skipped, returning empty output.
+pub fn run_git_mut(dir: &Path, args: &[&str]) -> Result<String, GitError> {
+    if dry_run::is_enabled() {
+        let dir = dir.to_string_lossy();
+        let mut full = vec!["-C", dir.as_ref()];
+        full.extend_from_slice(args);
+        dry_run::report(&dry_run::format_command("git", &full));
+        return Ok(String::new());
+    }
+    run_git_in_dir(dir, args)
+}
+
 /// Walk up from `start` looking for the bare-hub layout (`.bare` dir, or a `.git`
 /// file pointing at `./.bare`). Returns the hub root if found, `None` otherwise.
 ///
@@ -136,28 +151,37 @@ impl RepoContext {
 
     /// Parent directory under which named worktrees are created;
     /// `worktree_base().join(name)` is the worktree path.
-    /// Bare: `<hub_root>`. Standard: `<main_worktree>/.claude/worktrees` by
-    /// default, or the `worktree_base` config override (relative resolves
-    /// against the main worktree, absolute used as-is).
+    /// Bare: `<hub_root>`. Standard: `<main_worktree>/.claude/worktrees`. The
+    /// `worktree_base` config setting overrides both (see `expand_worktree_base`);
+    /// an invalid setting is reported and ignored.
     pub fn worktree_base(&self) -> PathBuf {
-        match &self.layout {
+        let default = match &self.layout {
             Layout::Bare { hub_root } => hub_root.clone(),
             Layout::Standard { main_worktree, .. } => {
-                match crate::config::load_config(self).worktree_base {
-                    Some(base) => {
-                        let pb = PathBuf::from(&base);
-                        if pb.is_absolute() {
-                            pb
-                        } else {
-                            main_worktree.join(pb)
-                        }
-                    }
-                    None => main_worktree.join(DEFAULT_STANDARD_WORKTREE_SUBDIR),
-                }
+                main_worktree.join(DEFAULT_STANDARD_WORKTREE_SUBDIR)
+            }
+        };
+        let Some(setting) = crate::config::load_config(self).worktree_base else {
+            return default;
+        };
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        match expand_worktree_base(&setting, self.anchor_dir(), home.as_deref()) {
+            Ok(base) => base,
+            Err(e) => {
+                eprintln!("Warning: ignoring worktree_base: {}", e);
+                default
             }
         }
     }
 
+    /// The repository's git directory: `.bare` for bare, the common `.git` for standard.
+    pub fn git_dir(&self) -> PathBuf {
+        match &self.layout {
+            Layout::Bare { hub_root } => hub_root.join(".bare"),
+            Layout::Standard { common_dir, .. } => common_dir.clone(),
+        }
+    }
+
     /// The main worktree path in standard mode, `None` in bare mode.
     pub fn main_worktree(&self) -> Option<&Path> {
         match &self.layout {
@@ -171,6 +195,60 @@ impl RepoContext {
     }
 }
 
+/// Expand a `worktree_base` setting into the directory worktrees are created in.
+///
+/// The setting is a directory, optionally ending in `/{name}` to read as a path
+/// template. `{hub}` is the repo root (`anchor`), `{repo}` its directory name,
+/// and a leading `~` the home directory. Relative results resolve against
+/// `anchor`. `{name}` anywhere but the last component is rejected, since
+/// worktrees are named after their directory.
+pub fn expand_worktree_base(
+    setting: &str,
+    anchor: &Path,
+    home: Option<&Path>,
+) -> Result<PathBuf, String> {
+    let trimmed = setting.trim().trim_end_matches('/');
+    let base = if trimmed == "{name}" {
+        ""
+    } else {
+        trimmed.strip_suffix("/{name}").unwrap_or(trimmed)
+    };
+    if base.contains("{name}") {
+        return Err(format!(
+            "'{}': {{name}} may only be the last path component",
+            setting
+        ));
+    }
+
+    let repo = anchor
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let expanded = base
+        .replace("{hub}", &anchor.to_string_lossy())
+        .replace("{repo}", &repo);
+    if let Some(start) = expanded.find('{') {
+        let placeholder = expanded[start..].split('}').next().unwrap_or_default();
+        return Err(format!(
+            "'{}': unknown placeholder {}}} (use {{hub}}, {{repo}} or {{name}})",
+            setting, placeholder
+        ));
+    }
+
+    let path = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = home.ok_or_else(|| format!("'{}': HOME is not set", setting))?;
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(&expanded),
+    };
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        anchor.join(path)
+    })
+}
+
 /// Resolve `p` to an absolute, canonical path. Relative paths resolve against
 /// `base`. Canonicalization falls back to the joined path when it fails (e.g.
 /// the path does not exist yet).
@@ -251,49 +329,59 @@ fn detect_repo_from(start: &Path) -> Result<RepoContext, GitError> {
 }
 
 /// Worktree information
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct Worktree {
     pub path: PathBuf,
     pub head: String,
     pub branch: Option<String>,
+    /// `Some(reason)` when the worktree is locked; the reason is empty when
+    /// none was given to `git worktree lock`.
+    pub locked: Option<String>,
+    /// `Some(reason)` when git considers the worktree prunable (e.g. its
+    /// directory is gone).
+    pub prunable: Option<String>,
 }
 
 /// Parse git worktree list --porcelain output into structured data
 pub fn parse_worktree_list(output: &str) -> Vec<Worktree> {
     let mut worktrees = Vec::new();
-    let mut current_path: Option<PathBuf> = None;
-    let mut current_head: Option<String> = None;
-    let mut current_branch: Option<String> = None;
+    let mut current: Option<Worktree> = None;
 
     for line in output.lines() {
         if let Some(path) = line.strip_prefix("worktree ") {
             // Save previous worktree if exists
-            if let (Some(path), Some(head)) = (current_path.take(), current_head.take()) {
-                worktrees.push(Worktree {
-                    path,
-                    head,
-                    branch: current_branch.take(),
-                });
+            if let Some(wt) = current.take().filter(|wt| !wt.head.is_empty()) {
+                worktrees.push(wt);
             }
-            current_path = Some(PathBuf::from(path));
-            current_branch = None;
-        } else if let Some(head) = line.strip_prefix("HEAD ") {
-            current_head = Some(head.to_string());
+            current = Some(Worktree {
+                path: PathBuf::from(path),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(wt) = current.as_mut() else {
+            continue;
+        };
+        if let Some(head) = line.strip_prefix("HEAD ") {
+            wt.head = head.to_string();
         } else if let Some(branch) = line.strip_prefix("branch ") {
-            current_branch = Some(branch.to_string());
+            wt.branch = Some(branch.to_string());
         } else if line == "bare" {
             // Mark bare repo
-            current_head = Some("(bare)".to_string());
+            wt.head = "(bare)".to_string();
+        } else if line == "locked" || line.starts_with("locked ") {
+            let reason = line.strip_prefix("locked").unwrap_or_default().trim();
+            wt.locked = Some(reason.to_string());
+        } else if line == "prunable" || line.starts_with("prunable ") {
+            let reason = line.strip_prefix("prunable").unwrap_or_default().trim();
+            wt.prunable = Some(reason.to_string());
         }
     }
 
     // Don't forget the last one
-    if let (Some(path), Some(head)) = (current_path, current_head) {
-        worktrees.push(Worktree {
-            path,
-            head,
-            branch: current_branch,
-        });
+    if let Some(wt) = current.filter(|wt| !wt.head.is_empty()) {
+        worktrees.push(wt);
     }
 
     worktrees
@@ -312,6 +400,56 @@ pub fn get_default_branch(hub_root: &Path) -> Option<String> {
     output.strip_prefix("refs/heads/").map(|s| s.to_string())
 }
 
+/// True when a local branch `refs/heads/<branch>` exists.
+pub fn local_branch_exists(dir: &Path, branch: &str) -> bool {
+    let full_ref = format!("refs/heads/{}", branch);
+    run_git_in_dir(dir, &["show-ref", "--verify", "--quiet", &full_ref]).is_ok()
+}
+
+/// Parse `git for-each-ref --format=%(refname:short) refs/remotes` output into the
+/// remote-tracking refs (e.g. `origin/feature`) whose branch part is `branch`.
+/// Symbolic `<remote>/HEAD` entries are skipped.
+pub fn parse_remote_branches(output: &str, branch: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            line.split_once('/')
+                .map(|(_, rest)| rest == branch && rest != "HEAD")
+                .unwrap_or(false)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Remote-tracking refs (e.g. `origin/feature`) for a branch name, across all remotes.
+pub fn remote_branches(dir: &Path, branch: &str) -> Result<Vec<String>, GitError> {
+    let output = run_git_in_dir(
+        dir,
+        &["for-each-ref", "--format=%(refname:short)", "refs/remotes"],
+    )?;
+    Ok(parse_remote_branches(&output, branch))
+}
+
+/// The repository's default branch in either layout.
+///
+/// Bare: `HEAD` of the bare repo (see `get_default_branch`). Standard: the
+/// remote default `refs/remotes/origin/HEAD`, because the main worktree's `HEAD`
+/// is just whatever happens to be checked out there. `None` when unknown.
+pub fn get_repo_default_branch(ctx: &RepoContext) -> Option<String> {
+    if !ctx.is_standard() {
+        return get_default_branch(ctx.anchor_dir());
+    }
+    let output = run_git_in_dir(
+        ctx.anchor_dir(),
+        &["symbolic-ref", "refs/remotes/origin/HEAD"],
+    )
+    .ok()?;
+    output
+        .strip_prefix("refs/remotes/origin/")
+        .map(|s| s.to_string())
+}
+
 /// Get the name of the current worktree based on the current directory
 /// Returns None if not currently in a worktree (e.g., in the hub root)
 pub fn get_current_worktree_name(hub_root: &Path) -> Result<Option<String>, GitError> {
@@ -323,29 +461,34 @@ pub fn get_current_worktree_name(hub_root: &Path) -> Result<Option<String>, GitE
         .unwrap_or_else(|_| current_dir.clone());
 
     let worktrees = get_worktree_list(hub_root)?;
+    Ok(find_worktree_containing(&worktrees, &current_dir)
+        .and_then(|wt| wt.path.file_name())
+        .map(|name| name.to_string_lossy().to_string()))
+}
 
-    for wt in worktrees {
+/// Find the worktree that contains `dir`. When worktrees are nested (standard
+/// layout: linked worktrees live under the main worktree), the deepest match
+/// wins, so a linked worktree is never mistaken for the main one.
+pub fn find_worktree_containing<'a>(worktrees: &'a [Worktree], dir: &Path) -> Option<&'a Worktree> {
+    worktrees
+        .iter()
         // Skip the bare repo entry
-        if wt.head == "(bare)" {
-            continue;
-        }
-
-        let wt_path = wt.path.canonicalize().unwrap_or_else(|_| wt.path.clone());
-
-        // Check if current dir is the worktree or inside it
-        if current_dir.starts_with(&wt_path) {
-            if let Some(name) = wt.path.file_name() {
-                return Ok(Some(name.to_string_lossy().to_string()));
-            }
-        }
-    }
-
-    Ok(None)
+        .filter(|wt| wt.head != "(bare)")
+        .filter_map(|wt| {
+            let wt_path = wt.path.canonicalize().unwrap_or_else(|_| wt.path.clone());
+            // Check if dir is the worktree or inside it
+            dir.starts_with(&wt_path)
+                .then(|| (wt_path.components().count(), wt))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, wt)| wt)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{git, init_repo};
+    use tempfile::TempDir;
 
     #[test]
     fn test_parse_worktree_list_empty() {
@@ -417,6 +560,76 @@ branch refs/heads/feature-branch
         assert!(result[0].branch.is_none());
     }
 
+    #[test]
+    fn test_parse_worktree_list_locked_and_prunable() {
+        let output = "\
+worktree /home/user/project/locked
+HEAD abc1234567890def
+branch refs/heads/locked
+locked
+
+worktree /home/user/project/reason
+HEAD abc1234567890def
+branch refs/heads/reason
+locked on a usb drive
+
+worktree /home/user/project/gone
+HEAD def4567890abc123
+detached
+prunable gitdir file points to non-existent location
+";
+        let result = parse_worktree_list(output);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].locked.as_deref(), Some(""));
+        assert!(result[0].prunable.is_none());
+        assert_eq!(result[1].locked.as_deref(), Some("on a usb drive"));
+        assert!(result[2].locked.is_none());
+        assert!(result[2].branch.is_none());
+        assert_eq!(
+            result[2].prunable.as_deref(),
+            Some("gitdir file points to non-existent location")
+        );
+    }
+
+    #[test]
+    fn test_find_worktree_containing_prefers_deepest() {
+        let output = "\
+worktree /project
+HEAD abc1234567890def
+branch refs/heads/main
+
+worktree /project/.claude/worktrees/feat
+HEAD def4567890abc123
+branch refs/heads/feat
+";
+        let worktrees = parse_worktree_list(output);
+
+        let inner = Path::new("/project/.claude/worktrees/feat/src");
+        let found = find_worktree_containing(&worktrees, inner).unwrap();
+        assert_eq!(found.path, PathBuf::from("/project/.claude/worktrees/feat"));
+
+        let outer = Path::new("/project/src");
+        let found = find_worktree_containing(&worktrees, outer).unwrap();
+        assert_eq!(found.path, PathBuf::from("/project"));
+
+        assert!(find_worktree_containing(&worktrees, Path::new("/elsewhere")).is_none());
+    }
+
+    #[test]
+    fn test_parse_remote_branches() {
+        let output = "origin/HEAD\norigin/main\norigin/feat\nupstream/feat\norigin/team/feat\n";
+        assert_eq!(
+            parse_remote_branches(output, "feat"),
+            vec!["origin/feat", "upstream/feat"]
+        );
+        assert_eq!(
+            parse_remote_branches(output, "team/feat"),
+            vec!["origin/team/feat"]
+        );
+        assert!(parse_remote_branches(output, "HEAD").is_empty());
+        assert!(parse_remote_branches(output, "missing").is_empty());
+    }
+
     #[test]
     fn test_git_error_display() {
         let error = GitError::new("test error message");
@@ -434,41 +647,6 @@ branch refs/heads/feature-branch
 
     // --- RepoContext / detect_repo ---
 
-    use std::process::Stdio;
-    use tempfile::TempDir;
-
-    fn git(dir: &Path, args: &[&str]) {
-        let status = Command::new("git")
-            .current_dir(dir)
-            .args(args)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .expect("failed to spawn git");
-        assert!(status.success(), "git {:?} failed", args);
-    }
-
-    /// Create a standard repo with one empty commit, returns the temp dir.
-    fn init_standard_repo() -> TempDir {
-        let tmp = TempDir::new().unwrap();
-        git(tmp.path(), &["init", "-q"]);
-        git(
-            tmp.path(),
-            &[
-                "-c",
-                "user.email=t@t",
-                "-c",
-                "user.name=t",
-                "commit",
-                "--allow-empty",
-                "-q",
-                "-m",
-                "init",
-            ],
-        );
-        tmp
-    }
-
     #[test]
     fn test_repo_context_bare_paths() {
         let ctx = RepoContext {
@@ -501,6 +679,55 @@ branch refs/heads/feature-branch
         assert!(ctx.is_standard());
     }
 
+    #[test]
+    fn test_expand_worktree_base_templates() {
+        let hub = Path::new("/src/myrepo");
+        let home = Some(Path::new("/home/u"));
+        let expand = |s: &str| expand_worktree_base(s, hub, home).unwrap();
+        assert_eq!(expand("{hub}/wt/{name}"), PathBuf::from("/src/myrepo/wt"));
+        assert_eq!(
+            expand("~/worktrees/{repo}/{name}"),
+            PathBuf::from("/home/u/worktrees/myrepo")
+        );
+        assert_eq!(expand("wt"), PathBuf::from("/src/myrepo/wt"));
+        assert_eq!(
+            expand("../{repo}-wt/"),
+            PathBuf::from("/src/myrepo/../myrepo-wt")
+        );
+        assert_eq!(expand("/abs/base"), PathBuf::from("/abs/base"));
+        assert_eq!(expand("{name}"), PathBuf::from("/src/myrepo"));
+        assert_eq!(expand("~"), PathBuf::from("/home/u"));
+    }
+
+    #[test]
+    fn test_expand_worktree_base_rejects_bad_templates() {
+        let hub = Path::new("/src/myrepo");
+        let err = expand_worktree_base("{name}/src", hub, None).unwrap_err();
+        assert!(err.contains("last path component"));
+        let err = expand_worktree_base("{hub}/{branch}", hub, None).unwrap_err();
+        assert!(err.contains("unknown placeholder {branch}"));
+        assert!(expand_worktree_base("~/wt", hub, None).is_err());
+    }
+
+    #[test]
+    fn test_bare_worktree_base_from_config() {
+        let tmp = TempDir::new().unwrap();
+        let hub = tmp.path().join("hub");
+        std::fs::create_dir_all(hub.join(".wtree")).unwrap();
+        std::fs::write(
+            hub.join(".wtree/config.toml"),
+            "worktree_base = \"{hub}/wt/{name}\"\n",
+        )
+        .unwrap();
+        let ctx = RepoContext {
+            layout: Layout::Bare {
+                hub_root: hub.clone(),
+            },
+        };
+        assert_eq!(ctx.worktree_base(), hub.join("wt"));
+        assert_eq!(ctx.git_dir(), hub.join(".bare"));
+    }
+
     #[test]
     fn test_detect_bare_hub_layout() {
         // Reproduce the smoke `.git -> ./.bare` layout: a `.bare` git dir plus a
@@ -520,7 +747,7 @@ branch refs/heads/feature-branch
 
     #[test]
     fn test_detect_standard_repo() {
-        let tmp = init_standard_repo();
+        let tmp = init_repo();
         let ctx = detect_repo_from(tmp.path()).unwrap();
         let state_dir = ctx.state_dir();
         match &ctx.layout {
@@ -543,7 +770,7 @@ branch refs/heads/feature-branch
     fn test_detect_standard_from_linked_worktree() {
         // From inside a *linked* worktree, detection must still resolve the
         // primary worktree, not the linked one (adversarial finding #2).
-        let tmp = init_standard_repo();
+        let tmp = init_repo();
         git(
             tmp.path(),
             &["worktree", "add", "-q", "wt-linked", "-b", "feat"],
@@ -563,6 +790,14 @@ branch refs/heads/feature-branch
         }
     }
 
+    #[test]
+    fn test_local_branch_exists() {
+        let tmp = init_repo();
+        git(tmp.path(), &["branch", "feat"]);
+        assert!(local_branch_exists(tmp.path(), "feat"));
+        assert!(!local_branch_exists(tmp.path(), "missing"));
+    }
+
     #[test]
     fn test_detect_outside_repo_errors() {
         let tmp = TempDir::new().unwrap();
@@ -1,5 +1,6 @@
 use std::path::Path;
 
+use crate::dry_run;
 use crate::git::{GitError, RepoContext};
 
 /// Normalize a gitignore line for equivalence comparison: trim whitespace, drop
@@ -64,7 +65,7 @@ pub fn ensure_gitignore_entry(ctx: &RepoContext) -> Result<(), GitError> {
 }
 
 fn write_gitignore(path: &Path, content: &str) -> Result<(), GitError> {
-    std::fs::write(path, content)
+    dry_run::write_file(path, content)
         .map_err(|e| GitError::new(format!("Failed to write {}: {}", path.display(), e)))
 }
 
@@ -195,4 +196,21 @@ mod tests {
             "outside-worktree base must not touch .gitignore"
         );
     }
+
+    #[test]
+    fn test_worktree_base_template_inside_main() {
+        let tmp = TempDir::new().unwrap();
+        let ctx = standard_ctx(&tmp);
+        let state_dir = tmp.path().join(".git/wtree");
+        fs::create_dir_all(&state_dir).unwrap();
+        fs::write(
+            state_dir.join("config.toml"),
+            "worktree_base = \"{hub}/wt/{name}\"\n",
+        )
+        .unwrap();
+
+        ensure_gitignore_entry(&ctx).unwrap();
+        let content = fs::read_to_string(tmp.path().join(".gitignore")).unwrap();
+        assert_eq!(content, "wt/\n");
+    }
 }
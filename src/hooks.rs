@@ -2,7 +2,11 @@ use serde::Deserialize;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::cd;
+use crate::config::describe_toml_error;
+use crate::dry_run;
 use crate::git::RepoContext;
+use crate::worktree_env;
 
 /// Hook phase - determines error handling behavior
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,6 +17,7 @@ pub enum Phase {
 
 /// Configuration for a single command's hooks
 #[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CommandHooks {
     #[serde(default)]
     pub pre: Vec<String>,
@@ -22,6 +27,7 @@ pub struct CommandHooks {
 
 /// Root configuration loaded from .wtree/hooks.toml
 #[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HooksConfig {
     #[serde(default)]
     pub create: CommandHooks,
@@ -39,6 +45,8 @@ pub struct HookContext {
     pub worktree_path: PathBuf,
     pub hub_root: PathBuf,
     pub branch: Option<String>,
+    /// Env store that `KEY=VALUE` lines hooks write to `WT_ENV_FILE` are merged into.
+    pub exports_to: Option<PathBuf>,
 }
 
 impl HookContext {
@@ -55,8 +63,15 @@ impl HookContext {
             worktree_path: worktree_path.to_path_buf(),
             hub_root: hub_root.to_path_buf(),
             branch: branch.map(|s| s.to_string()),
+            exports_to: None,
         }
     }
+
+    /// Let hooks export variables into the worktree's environment (see `worktree_env`).
+    pub fn with_exports(mut self, store: PathBuf) -> Self {
+        self.exports_to = Some(store);
+        self
+    }
 }
 
 /// Error type for hook execution
@@ -81,14 +96,38 @@ impl HookError {
     }
 }
 
-/// Load hooks configuration from the layout's state dir (`hooks.toml`).
+/// The layout's hooks file.
 /// Bare: `<hub_root>/.wtree/hooks.toml`. Standard: `<common_dir>/wtree/hooks.toml`.
+pub fn hooks_path(ctx: &RepoContext) -> PathBuf {
+    ctx.state_dir().join("hooks.toml")
+}
+
+/// Parse a hooks file's `content`; errors read `path:line:column: message`.
+pub fn parse_hooks(path: &Path, content: &str) -> Result<HooksConfig, String> {
+    toml::from_str(content).map_err(|e| describe_toml_error(path, content, &e))
+}
+
+/// Load hooks configuration from `hooks_path`.
 /// Returns `None` when the file is absent (hooks are opt-in), so lazy-init
-/// standard repos that never ran `wt init` simply run no hooks.
-pub fn load_hooks(ctx: &RepoContext) -> Option<HooksConfig> {
-    let hooks_path = ctx.state_dir().join("hooks.toml");
-    let content = std::fs::read_to_string(&hooks_path).ok()?;
-    toml::from_str(&content).ok()
+/// standard repos that never ran `wt init` simply run no hooks. A file that
+/// cannot be read or parsed is an error: running the command without its
+/// hooks would silently skip setup the user relies on.
+pub fn load_hooks(ctx: &RepoContext) -> Result<Option<HooksConfig>, HookError> {
+    let path = hooks_path(ctx);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(HookError::new(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+    parse_hooks(&path, &content)
+        .map(Some)
+        .map_err(|e| HookError::new(format!("Invalid hooks file {}", e)))
 }
 
 /// Get hooks for a specific command
@@ -113,14 +152,22 @@ pub fn run_pre_hooks(config: &Option<HooksConfig>, context: &HookContext) -> Res
 
 /// Run post-hooks for a command. Logs warnings but doesn't return error.
 pub fn run_post_hooks(config: &Option<HooksConfig>, context: &HookContext) {
+    if let Err(e) = try_run_post_hooks(config, context) {
+        eprintln!("Warning: post-hook failed: {}", e);
+    }
+}
+
+/// Run post-hooks for a command, returning the failure for the caller to report.
+pub fn try_run_post_hooks(
+    config: &Option<HooksConfig>,
+    context: &HookContext,
+) -> Result<(), HookError> {
     let Some(config) = config else {
-        return;
+        return Ok(());
     };
 
     let hooks = get_command_hooks(config, &context.command);
-    if let Err(e) = run_hooks(&hooks.post, context, Phase::Post) {
-        eprintln!("Warning: post-hook failed: {}", e);
-    }
+    run_hooks(&hooks.post, context, Phase::Post)
 }
 
 /// Execute a list of hooks
@@ -132,6 +179,33 @@ fn run_hooks(hooks: &[String], context: &HookContext, phase: Phase) -> Result<()
 }
 
 /// Execute a single hook command
+/// The `sh -c` process running `hook` in `working_dir`, with the `WT_*`
+/// variables of `context` set and `WT_ENV_FILE` pointing at `export_file`.
+fn hook_command(
+    hook: &str,
+    working_dir: &Path,
+    context: &HookContext,
+    export_file: Option<&Path>,
+) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(hook)
+        .current_dir(working_dir)
+        // A `wt switch` inside a hook must not overwrite the outer command's cd target
+        .env_remove(cd::CD_FILE_VAR)
+        .env("WT_COMMAND", &context.command)
+        .env("WT_WORKTREE_NAME", &context.worktree_name)
+        .env(
+            "WT_WORKTREE_PATH",
+            context.worktree_path.to_string_lossy().as_ref(),
+        )
+        .env("WT_HUB_ROOT", context.hub_root.to_string_lossy().as_ref())
+        .envs(context.branch.as_ref().map(|b| ("WT_BRANCH", b.as_str())))
+        .envs(export_file.map(|f| (worktree_env::EXPORT_FILE_VAR, f.as_os_str())));
+    command
+}
+
 fn run_single_hook(hook: &str, context: &HookContext, phase: Phase) -> Result<(), HookError> {
     // Determine working directory based on phase
     let working_dir = match phase {
@@ -146,20 +220,44 @@ fn run_single_hook(hook: &str, context: &HookContext, phase: Phase) -> Result<()
         }
     };
 
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(hook)
-        .current_dir(working_dir)
-        .env("WT_COMMAND", &context.command)
-        .env("WT_WORKTREE_NAME", &context.worktree_name)
-        .env(
-            "WT_WORKTREE_PATH",
-            context.worktree_path.to_string_lossy().as_ref(),
-        )
-        .env("WT_HUB_ROOT", context.hub_root.to_string_lossy().as_ref())
-        .envs(context.branch.as_ref().map(|b| ("WT_BRANCH", b.as_str())))
-        .output()
-        .map_err(|e| HookError::new(format!("Failed to execute hook '{}': {}", hook, e)))?;
+    if dry_run::is_enabled() {
+        let phase_name = match phase {
+            Phase::Pre => "pre-hook",
+            Phase::Post => "post-hook",
+        };
+        dry_run::report(&format!(
+            "{} ({}) in {}: {}",
+            phase_name,
+            context.command,
+            working_dir.display(),
+            hook
+        ));
+        return Ok(());
+    }
+
+    // A fresh file per hook for `WT_ENV_FILE`, merged into the store on success
+    let export_file = context
+        .exports_to
+        .as_deref()
+        .map(worktree_env::create_export_file)
+        .transpose()
+        .map_err(|e| {
+            HookError::new(format!(
+                "Failed to create {} for hook '{}': {}",
+                worktree_env::EXPORT_FILE_VAR,
+                hook,
+                e
+            ))
+        })?;
+
+    let output = hook_command(hook, working_dir, context, export_file.as_deref()).output();
+    let exported = export_file.as_ref().and_then(|f| {
+        let content = std::fs::read_to_string(f).ok();
+        let _ = std::fs::remove_file(f);
+        content
+    });
+    let output =
+        output.map_err(|e| HookError::new(format!("Failed to execute hook '{}': {}", hook, e)))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -175,6 +273,15 @@ fn run_single_hook(hook: &str, context: &HookContext, phase: Phase) -> Result<()
         )));
     }
 
+    if let (Some(store), Some(exported)) = (&context.exports_to, exported) {
+        worktree_env::merge_exports(store, &exported).map_err(|e| {
+            HookError::new(format!(
+                "Failed to save variables exported by '{}': {}",
+                hook, e
+            ))
+        })?;
+    }
+
     Ok(())
 }
 
@@ -313,6 +420,37 @@ pre = ["remove-pre"]
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_run_hooks_exports_env() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = tmp.path().join("env/test.hooks.env");
+        let context = HookContext::new("create", "test", tmp.path(), tmp.path(), None)
+            .with_exports(store.clone());
+
+        let hooks = vec![
+            "echo PORT=3001 >> \"$WT_ENV_FILE\"".to_string(),
+            "echo NAME=$WT_WORKTREE_NAME >> \"$WT_ENV_FILE\"".to_string(),
+        ];
+        run_hooks(&hooks, &context, Phase::Post).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&store).unwrap(),
+            "NAME=\"test\"\nPORT=\"3001\"\n"
+        );
+    }
+
+    #[test]
+    fn test_hooks_do_not_inherit_cd_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let context = HookContext::new("create", "test", tmp.path(), tmp.path(), None);
+        let command = hook_command("true", tmp.path(), &context, None);
+        let cd_file = command
+            .get_envs()
+            .find(|(key, _)| *key == cd::CD_FILE_VAR)
+            .map(|(_, value)| value);
+        // Removed from the child's environment, not merely left unset
+        assert_eq!(cd_file, Some(None));
+    }
+
     #[test]
     fn test_run_pre_hooks_no_config() {
         let context = HookContext::new("create", "test", &env::temp_dir(), &env::temp_dir(), None);
@@ -335,6 +473,45 @@ pre = ["remove-pre"]
             },
         };
         let result = load_hooks(&ctx);
-        assert!(result.is_none());
+        assert!(matches!(result, Ok(None)));
+    }
+
+    #[test]
+    fn test_load_hooks_invalid_file_is_located_error() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let ctx = RepoContext {
+            layout: crate::git::Layout::Bare {
+                hub_root: tmp.path().to_path_buf(),
+            },
+        };
+        std::fs::create_dir_all(ctx.state_dir()).unwrap();
+        std::fs::write(
+            hooks_path(&ctx),
+            "[create]\npost = [\"npm install\"]\n\n[crate]\npre = []\n",
+        )
+        .unwrap();
+
+        let err = load_hooks(&ctx).unwrap_err();
+        assert!(
+            err.message
+                .contains("hooks.toml:4:2: unknown field `crate`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_parse_hooks_rejects_unknown_phase() {
+        let err = parse_hooks(
+            Path::new("hooks.toml"),
+            "[switch]\npost = []\nport = [\"x\"]\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.starts_with("hooks.toml:3:1: unknown field `port`"),
+            "{}",
+            err
+        );
+        assert!(parse_hooks(Path::new("hooks.toml"), "[remove]\npre = [\"true\"]\n").is_ok());
     }
 }
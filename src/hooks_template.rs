@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use crate::config::global_dirs;
+
 /// Template content for hooks.toml with commented examples.
 pub const HOOKS_TEMPLATE: &str = r#"# wtree hooks configuration
 # Define pre/post commands for create, switch, and remove operations.
@@ -16,6 +18,8 @@ pub const HOOKS_TEMPLATE: &str = r#"# wtree hooks configuration
 #   WT_WORKTREE_PATH  - Absolute path to target worktree
 #   WT_HUB_ROOT       - Path to hub root (parent of .bare)
 #   WT_BRANCH         - Branch name (create only, if specified)
+#   WT_ENV_FILE       - Append KEY=VALUE lines to set them in the worktree's
+#                       shell environment (create/switch only)
 
 [create]
 # pre = []
@@ -30,13 +34,23 @@ pub const HOOKS_TEMPLATE: &str = r#"# wtree hooks configuration
 # post = []
 "#;
 
-/// Get the path to the global default hooks file (~/.wtree/default-hooks.toml)
-fn get_global_default_hooks_path() -> Option<PathBuf> {
-    std::env::var("HOME").ok().map(|home| {
-        PathBuf::from(home)
-            .join(".wtree")
-            .join("default-hooks.toml")
-    })
+/// Get the path to the global default hooks file: the first `default-hooks.toml`
+/// that exists in the global config dirs, else the legacy `~/.wtree` location.
+pub fn get_global_default_hooks_path() -> Option<PathBuf> {
+    default_hooks_path_in(&global_dirs())
+}
+
+/// `get_global_default_hooks_path` with the global directories `dirs`.
+fn default_hooks_path_in(dirs: &[PathBuf]) -> Option<PathBuf> {
+    let candidates: Vec<PathBuf> = dirs
+        .iter()
+        .map(|dir| dir.join("default-hooks.toml"))
+        .collect();
+    candidates
+        .iter()
+        .find(|p| p.is_file())
+        .or(candidates.last())
+        .cloned()
 }
 
 /// Read global default hooks configuration if it exists
@@ -46,7 +60,7 @@ pub fn read_global_default_hooks() -> Option<String> {
 }
 
 /// Resolve the hooks file content to drop into a newly managed repo: the global
-/// default from `~/.wtree/default-hooks.toml` if present, otherwise the built-in
+/// default `default-hooks.toml` if present, otherwise the built-in
 /// commented template. Shared by `wt clone` and `wt init`.
 pub fn default_hooks_content() -> String {
     read_global_default_hooks().unwrap_or_else(|| HOOKS_TEMPLATE.to_string())
@@ -55,21 +69,28 @@ pub fn default_hooks_content() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+    use tempfile::TempDir;
 
-    // These assertions share one test because they mutate the process-global
-    // `HOME` env var; splitting them lets Rust's parallel test runner clobber
-    // `HOME` across threads. Keeping them sequential in one function avoids that
-    // race without pulling in a serial-test dependency.
     #[test]
     fn test_global_hooks_path_and_fallback() {
-        std::env::set_var("HOME", "/home/testuser");
-        let path = get_global_default_hooks_path();
+        let home = TempDir::new().unwrap();
+        let dirs = vec![home.path().join("xdg"), home.path().join(".wtree")];
+
+        // Nothing there: the legacy location
+        assert_eq!(
+            default_hooks_path_in(&dirs),
+            Some(home.path().join(".wtree/default-hooks.toml"))
+        );
+        fs::create_dir_all(&dirs[0]).unwrap();
+        fs::write(dirs[0].join("default-hooks.toml"), "").unwrap();
         assert_eq!(
-            path.unwrap().to_str().unwrap(),
-            "/home/testuser/.wtree/default-hooks.toml"
+            default_hooks_path_in(&dirs),
+            Some(home.path().join("xdg/default-hooks.toml"))
         );
+        assert_eq!(default_hooks_path_in(&[]), None);
 
-        std::env::set_var("HOME", "/nonexistent/path");
+        // Unit tests see no global files, so the built-in template is used
         assert!(read_global_default_hooks().is_none());
         assert!(default_hooks_content().contains("wtree hooks configuration"));
     }
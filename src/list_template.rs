@@ -0,0 +1,409 @@
+use std::io::IsTerminal;
+
+use crate::commands::history::format_age;
+use crate::commands::list::ListEntry;
+use crate::git::GitError;
+
+/// Fields a `wt list --format` template can reference: the JSON schema of
+/// `ListEntry` (status fields included), plus `age`, the relative age of the
+/// last commit.
+pub const FIELDS: &[&str] = &[
+    "name",
+    "path",
+    "branch",
+    "head",
+    "is_main",
+    "is_default",
+    "is_current",
+    "locked",
+    "lock_reason",
+    "prunable",
+    "prunable_reason",
+    "staged",
+    "modified",
+    "untracked",
+    "conflicted",
+    "upstream",
+    "upstream_gone",
+    "ahead",
+    "behind",
+    "default_ahead",
+    "default_behind",
+    "last_commit_time",
+    "last_commit_subject",
+    "age",
+];
+
+/// Fields that are only known once worktree status has been collected.
+const STATUS_FIELDS: &[&str] = &[
+    "staged",
+    "modified",
+    "untracked",
+    "conflicted",
+    "upstream",
+    "upstream_gone",
+    "ahead",
+    "behind",
+    "default_ahead",
+    "default_behind",
+    "last_commit_time",
+    "last_commit_subject",
+    "age",
+];
+
+/// Placeholder for a missing value (detached branch, no upstream, ...).
+const MISSING: &str = "-";
+
+/// A parsed piece of a list template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Literal(String),
+    /// `{name}`, `{name:20}` (left-aligned) or `{name:>20}` (right-aligned).
+    /// Without an explicit width the column is as wide as its widest value.
+    Field {
+        name: String,
+        width: Option<usize>,
+        right: bool,
+    },
+}
+
+/// Parse a template such as `{name:20} {branch} {ahead}/{behind}`. `{{` and
+/// `}}` are literal braces. Unknown fields are an error listing the valid ones.
+pub fn parse_template(template: &str) -> Result<Vec<Segment>, GitError> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => {
+                            return Err(GitError::new(format!(
+                                "Unclosed '{{' in list format '{}'",
+                                template
+                            )))
+                        }
+                    }
+                }
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(parse_field(&spec)?);
+            }
+            '}' => {
+                return Err(GitError::new(format!(
+                    "Unmatched '}}' in list format '{}' (use '}}}}' for a literal brace)",
+                    template
+                )))
+            }
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+fn parse_field(spec: &str) -> Result<Segment, GitError> {
+    let (name, width_spec) = match spec.split_once(':') {
+        Some((name, width)) => (name.trim(), Some(width.trim())),
+        None => (spec.trim(), None),
+    };
+
+    if !FIELDS.contains(&name) {
+        return Err(GitError::new(format!(
+            "Unknown list format field '{}'. Available fields: {}",
+            name,
+            FIELDS.join(", ")
+        )));
+    }
+
+    let (width, right) = match width_spec {
+        None => (None, false),
+        Some(w) => {
+            let (digits, right) = match w.strip_prefix('>') {
+                Some(d) => (d, true),
+                None => (w.strip_prefix('<').unwrap_or(w), false),
+            };
+            let width = digits.parse().map_err(|_| {
+                GitError::new(format!("Invalid width '{}' for field '{}'", w, name))
+            })?;
+            (Some(width), right)
+        }
+    };
+
+    Ok(Segment::Field {
+        name: name.to_string(),
+        width,
+        right,
+    })
+}
+
+/// True when any referenced field requires `git status` to be collected.
+pub fn needs_status(segments: &[Segment]) -> bool {
+    segments.iter().any(|s| match s {
+        Segment::Field { name, .. } => STATUS_FIELDS.contains(&name.as_str()),
+        Segment::Literal(_) => false,
+    })
+}
+
+fn opt<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| MISSING.to_string())
+}
+
+/// The display value of `field` for `entry`. `now` is the Unix time `age` is
+/// measured against.
+pub fn field_value(entry: &ListEntry, field: &str, now: u64) -> String {
+    let status = entry.status.as_ref();
+    match field {
+        "name" => entry.name.clone(),
+        "path" => entry.path.display().to_string(),
+        "branch" => opt(entry.branch.as_ref()),
+        "head" => entry.head.clone(),
+        "is_main" => entry.is_main.to_string(),
+        "is_default" => entry.is_default.to_string(),
+        "is_current" => entry.is_current.to_string(),
+        "locked" => entry.locked.to_string(),
+        "lock_reason" => opt(entry.lock_reason.as_ref()),
+        "prunable" => entry.prunable.to_string(),
+        "prunable_reason" => opt(entry.prunable_reason.as_ref()),
+        "staged" => opt(status.map(|s| s.staged)),
+        "modified" => opt(status.map(|s| s.modified)),
+        "untracked" => opt(status.map(|s| s.untracked)),
+        "conflicted" => opt(status.map(|s| s.conflicted)),
+        "upstream" => opt(status.and_then(|s| s.upstream.as_ref())),
+        "upstream_gone" => opt(status.map(|s| s.upstream_gone)),
+        "ahead" => opt(status.and_then(|s| s.ahead)),
+        "behind" => opt(status.and_then(|s| s.behind)),
+        "default_ahead" => opt(status.and_then(|s| s.default_ahead)),
+        "default_behind" => opt(status.and_then(|s| s.default_behind)),
+        "last_commit_time" => opt(status.and_then(|s| s.last_commit_time)),
+        "last_commit_subject" => opt(status.and_then(|s| s.last_commit_subject.as_ref())),
+        "age" => opt(status
+            .and_then(|s| s.last_commit_time)
+            .map(|t| format_age(now.saturating_sub(t)))),
+        _ => MISSING.to_string(),
+    }
+}
+
+/// ANSI colour for a rendered value, if the field has one.
+fn field_color(entry: &ListEntry, field: &str, value: &str) -> Option<&'static str> {
+    let nonzero = value != "0" && value != MISSING;
+    match field {
+        "name" if entry.is_current => Some("1"),
+        "branch" => Some("36"),
+        "head" => Some("2"),
+        "ahead" | "default_ahead" | "staged" if nonzero => Some("32"),
+        "behind" | "default_behind" | "conflicted" if nonzero => Some("31"),
+        "modified" | "untracked" if nonzero => Some("33"),
+        "locked" | "prunable" | "upstream_gone" if value == "true" => Some("33"),
+        _ => None,
+    }
+}
+
+/// Whether to colour output: stdout is a terminal and `NO_COLOR` is unset or empty.
+pub fn color_enabled() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && std::io::stdout().is_terminal()
+}
+
+/// Render one line per entry. Fields without an explicit width are padded to
+/// their widest value so columns line up, except a field that ends the line.
+/// Padding is computed on the plain text, so colour codes never skew columns.
+pub fn render(segments: &[Segment], entries: &[ListEntry], now: u64, color: bool) -> Vec<String> {
+    let values: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            segments
+                .iter()
+                .map(|s| match s {
+                    Segment::Field { name, .. } => field_value(entry, name, now),
+                    Segment::Literal(_) => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let last_field = segments
+        .iter()
+        .rposition(|s| matches!(s, Segment::Field { .. }));
+    let trailing_literal = last_field
+        .map(|i| {
+            segments[i + 1..].iter().any(|s| match s {
+                Segment::Literal(l) => !l.trim().is_empty(),
+                Segment::Field { .. } => true,
+            })
+        })
+        .unwrap_or(false);
+
+    let widths: Vec<usize> = segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| match s {
+            Segment::Field { width: Some(w), .. } => *w,
+            Segment::Field { width: None, .. } if Some(i) == last_field && !trailing_literal => 0,
+            Segment::Field { width: None, .. } => values
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0),
+            Segment::Literal(_) => 0,
+        })
+        .collect();
+
+    entries
+        .iter()
+        .zip(&values)
+        .map(|(entry, row)| {
+            let mut line = String::new();
+            for (i, segment) in segments.iter().enumerate() {
+                match segment {
+                    Segment::Literal(l) => line.push_str(l),
+                    Segment::Field { name, right, .. } => {
+                        let value = &row[i];
+                        let pad = " ".repeat(widths[i].saturating_sub(value.chars().count()));
+                        let shown = match field_color(entry, name, value) {
+                            Some(code) if color => format!("\x1b[{}m{}\x1b[0m", code, value),
+                            _ => value.clone(),
+                        };
+                        if *right {
+                            line.push_str(&pad);
+                            line.push_str(&shown);
+                        } else {
+                            line.push_str(&shown);
+                            line.push_str(&pad);
+                        }
+                    }
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::WorktreeStatus;
+    use std::path::PathBuf;
+
+    fn entry(name: &str, branch: Option<&str>, status: Option<WorktreeStatus>) -> ListEntry {
+        ListEntry {
+            name: name.to_string(),
+            path: PathBuf::from(format!("/hub/{}", name)),
+            branch: branch.map(|b| b.to_string()),
+            head: "abc1234".to_string(),
+            is_main: false,
+            is_default: false,
+            is_current: false,
+            locked: false,
+            lock_reason: None,
+            prunable: false,
+            prunable_reason: None,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_parse_template_fields_and_literals() {
+        let segments = parse_template("{name:20} [{branch}] {ahead:>3}").unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Field {
+                    name: "name".to_string(),
+                    width: Some(20),
+                    right: false
+                },
+                Segment::Literal(" [".to_string()),
+                Segment::Field {
+                    name: "branch".to_string(),
+                    width: None,
+                    right: false
+                },
+                Segment::Literal("] ".to_string()),
+                Segment::Field {
+                    name: "ahead".to_string(),
+                    width: Some(3),
+                    right: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_template_escaped_braces() {
+        let segments = parse_template("{{{name}}}").unwrap();
+        assert_eq!(segments[0], Segment::Literal("{".to_string()));
+        assert_eq!(segments[2], Segment::Literal("}".to_string()));
+    }
+
+    #[test]
+    fn test_parse_template_errors() {
+        let err = parse_template("{nmae}").unwrap_err();
+        assert!(err.message.contains("Unknown list format field 'nmae'"));
+        assert!(parse_template("{name").is_err());
+        assert!(parse_template("name}").is_err());
+        assert!(parse_template("{name:wide}").is_err());
+    }
+
+    #[test]
+    fn test_needs_status() {
+        assert!(!needs_status(&parse_template("{name} {branch}").unwrap()));
+        assert!(needs_status(&parse_template("{name} {ahead}").unwrap()));
+        assert!(needs_status(&parse_template("{age}").unwrap()));
+    }
+
+    #[test]
+    fn test_render_aligns_columns() {
+        let status = WorktreeStatus {
+            ahead: Some(2),
+            behind: Some(0),
+            last_commit_time: Some(0),
+            ..Default::default()
+        };
+        let entries = vec![
+            entry("main", Some("main"), Some(status)),
+            entry("feature-long", None, None),
+        ];
+        let segments = parse_template("{name} {branch} {ahead}/{behind} {age}").unwrap();
+        let lines = render(&segments, &entries, 3 * 3600, false);
+        assert_eq!(
+            lines,
+            vec!["main         main 2/0 3h ago", "feature-long -    -/- -"]
+        );
+    }
+
+    #[test]
+    fn test_render_explicit_widths() {
+        let entries = vec![entry("a", Some("dev"), None)];
+        let segments = parse_template("{name:4}|{branch:>5}|").unwrap();
+        assert_eq!(render(&segments, &entries, 0, false), vec!["a   |  dev|"]);
+    }
+
+    #[test]
+    fn test_render_color_does_not_skew_padding() {
+        let entries = vec![entry("a", Some("dev"), None), entry("bb", Some("x"), None)];
+        let segments = parse_template("{branch} {name}").unwrap();
+        let lines = render(&segments, &entries, 0, true);
+        assert_eq!(lines[0], "\x1b[36mdev\x1b[0m a");
+        assert_eq!(lines[1], "\x1b[36mx\x1b[0m   bb");
+    }
+}
@@ -1,17 +1,30 @@
+mod cd;
 mod commands;
 mod config;
+mod confirm;
+mod dry_run;
 mod git;
 mod gitignore;
 mod hooks;
 mod hooks_template;
+mod list_template;
+mod parallel;
 mod state;
+mod status;
+#[cfg(test)]
+mod test_util;
+mod trash;
+mod worktree_env;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(name = "wt")]
 #[command(about = "A git worktree wrapper for bare and standard repositories")]
 struct Cli {
+    /// Show the git commands, file writes and hooks that would run, without running them
+    #[arg(long, global = true)]
+    dry_run: bool,
     #[command(subcommand)]
     command: Command,
 }
@@ -31,15 +44,44 @@ enum Command {
     /// Output shell integration script
     #[command(hide = true)]
     ShellInit {
-        /// Shell type (bash or zsh)
+        /// Shell type (bash, zsh, fish, nu or pwsh)
         shell: String,
     },
+    /// Output shell completions (bash, zsh or fish)
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: commands::completions::CompletionShell,
+    },
+    /// List completion candidates at runtime (used by the completion scripts)
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        kind: commands::completions::CompletionKind,
+    },
     /// Switch to a worktree
     #[command(visible_alias = "sw")]
     Switch {
+        /// Worktree name, `-` for the previous worktree, or `-N` to go N entries back
+        #[arg(allow_negative_numbers = true)]
+        name: String,
+        /// Land at the worktree root instead of the current relative subdirectory
+        #[arg(long)]
+        root: bool,
+        /// Create the worktree (checking out an existing branch of that name) if it does not exist
+        #[arg(short, long)]
+        create: bool,
+    },
+    /// Switch to a worktree, creating it first if it does not exist
+    Go {
         /// Worktree name
         name: String,
+        /// Land at the worktree root instead of the current relative subdirectory
+        #[arg(long)]
+        root: bool,
     },
+    /// Show recently visited worktrees (most recent first)
+    History,
     /// Create a new worktree
     #[command(visible_alias = "c")]
     Create {
@@ -55,14 +97,74 @@ enum Command {
         #[arg(short, long)]
         switch: bool,
     },
+    /// Switch to the worktree that has a branch checked out, creating one if needed
+    #[command(visible_alias = "co")]
+    Checkout {
+        /// Branch name (local, on a remote, or new)
+        branch: String,
+    },
     /// List all worktrees
     #[command(visible_alias = "ls")]
-    List,
+    List {
+        /// Print a JSON array with a stable schema
+        #[arg(long, conflicts_with = "porcelain")]
+        json: bool,
+        /// Print stable `key value` blocks for scripts
+        #[arg(long)]
+        porcelain: bool,
+        /// Show changes, ahead/behind, last commit and lock state per worktree
+        #[arg(long)]
+        status: bool,
+        /// Template such as '{name:20} {branch} {ahead}/{behind} {age}'
+        #[arg(long, conflicts_with_all = ["json", "porcelain"])]
+        format: Option<String>,
+    },
+    /// Print a compact `hub:worktree` segment for shell prompts (nothing outside a repo)
+    Prompt {
+        /// Append `*` when the worktree has uncommitted changes (runs `git status`)
+        #[arg(long)]
+        dirty: bool,
+    },
+    /// Show or change settings in config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Per-worktree environment variables, applied by the shell integration on cd
+    Env {
+        #[command(subcommand)]
+        action: EnvAction,
+    },
+    /// Show disk usage per worktree and what 'wt prune' would free
+    Du {
+        /// Sort by a column (sizes sort largest first)
+        #[arg(long, value_enum)]
+        sort: Option<commands::du::SortKey>,
+    },
     /// Remove worktrees
     #[command(visible_alias = "rm")]
     Remove {
-        /// Worktree names to remove
+        /// Worktree names to remove; `.` or no name removes the current worktree
         names: Vec<String>,
+        /// Remove even with uncommitted changes or unpushed commits
+        #[arg(short, long)]
+        force: bool,
+        /// Also delete the local branch if it is merged (or always, with --force)
+        #[arg(short = 'D', long)]
+        delete_branch: bool,
+    },
+    /// Clean up stale worktree entries, orphan directories and history
+    Gc {
+        /// Delete orphan directories without asking
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// List removed worktrees that can be restored
+    Trash,
+    /// Recreate a removed worktree with its uncommitted changes
+    Restore {
+        /// Name of the removed worktree (see 'wt trash')
+        name: String,
     },
     /// Remove all worktrees except the default one
     #[command(visible_alias = "p")]
@@ -70,26 +172,170 @@ enum Command {
         /// Skip confirmation prompt
         #[arg(short, long)]
         force: bool,
+        /// Only worktrees whose branch is merged into the default branch
+        #[arg(long)]
+        merged: bool,
+        /// Only worktrees whose upstream branch was deleted
+        #[arg(long)]
+        gone: bool,
+        /// Only worktrees with no commits or switches for this long (e.g. 30d, 12h, 2w)
+        #[arg(long, value_name = "AGE", value_parser = commands::prune::parse_age)]
+        older_than: Option<u64>,
+        /// Skip worktrees with uncommitted changes
+        #[arg(long)]
+        clean_only: bool,
+        /// Remove up to N worktrees at once, running their remove hooks in parallel
+        #[arg(short, long, value_name = "N", default_value_t = 1)]
+        jobs: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Show effective settings and the file each comes from
+    #[command(visible_alias = "ls")]
+    List,
+    /// Print the effective value of a setting
+    Get {
+        /// Setting name, e.g. trash_days or env.PORT
+        key: String,
+    },
+    /// Change a setting, keeping the file's comments and formatting
+    Set {
+        /// Setting name, e.g. trash_days or env.PORT
+        key: String,
+        value: String,
+        /// Write the global config instead of this repository's
+        #[arg(long)]
+        global: bool,
+    },
+    /// Open the config file in $VISUAL or $EDITOR
+    Edit {
+        /// Edit the global config instead of this repository's
+        #[arg(long)]
+        global: bool,
+    },
+    /// Check config and hooks files for syntax errors, unknown keys and bad values
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum EnvAction {
+    /// Set variables for a worktree
+    Set {
+        /// KEY=VALUE pairs
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        pairs: Vec<String>,
+        /// Worktree name (default: the current worktree)
+        #[arg(short, long)]
+        worktree: Option<String>,
+    },
+    /// Remove variables set with 'wt env set'
+    Unset {
+        /// Variable names
+        #[arg(required = true)]
+        names: Vec<String>,
+        /// Worktree name (default: the current worktree)
+        #[arg(short, long)]
+        worktree: Option<String>,
+    },
+    /// Show a worktree's variables and where each comes from
+    #[command(visible_alias = "ls")]
+    List {
+        /// Worktree name (default: the current worktree)
+        #[arg(short, long)]
+        worktree: Option<String>,
+    },
+    /// Print shell commands that apply the current worktree's variables and undo the previous ones
+    Activate {
+        #[arg(value_enum)]
+        shell: commands::env::EnvShell,
     },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    if cli.dry_run {
+        dry_run::enable();
+    }
 
     match cli.command {
         Command::Clone { url, switch } => commands::clone::run(&url, switch)?,
         Command::Init => commands::init::run()?,
         Command::ShellInit { shell } => commands::shell_init::run(&shell)?,
-        Command::Switch { name } => commands::switch::run(&name)?,
+        Command::Completions { shell } => commands::completions::run(shell, Cli::command())?,
+        Command::Complete { kind } => commands::completions::run_complete(kind)?,
+        Command::Switch { name, root, create } => commands::switch::run(&name, root, create)?,
+        Command::Go { name, root } => commands::switch::run(&name, root, true)?,
         Command::Create {
             name,
             checkout,
             base,
             switch,
         } => commands::create::run(&name, checkout.as_deref(), base.as_deref(), switch)?,
-        Command::List => commands::list::run()?,
-        Command::Remove { names } => commands::remove::run(&names)?,
-        Command::Prune { force } => commands::prune::run(force)?,
+        Command::Checkout { branch } => commands::checkout::run(&branch)?,
+        Command::History => commands::history::run()?,
+        Command::List {
+            json,
+            porcelain,
+            status,
+            format: template,
+        } => {
+            let format = if json {
+                commands::list::ListFormat::Json
+            } else if porcelain {
+                commands::list::ListFormat::Porcelain
+            } else {
+                commands::list::ListFormat::Text
+            };
+            commands::list::run(format, status, template.as_deref())?
+        }
+        Command::Prompt { dirty } => commands::prompt::run(dirty)?,
+        Command::Config { action } => match action {
+            ConfigAction::List => commands::config::run_list()?,
+            ConfigAction::Get { key } => commands::config::run_get(&key)?,
+            ConfigAction::Set { key, value, global } => {
+                commands::config::run_set(&key, &value, global)?
+            }
+            ConfigAction::Edit { global } => commands::config::run_edit(global)?,
+            ConfigAction::Validate => commands::config::run_validate()?,
+        },
+        Command::Env { action } => match action {
+            EnvAction::Set { pairs, worktree } => {
+                commands::env::run_set(&pairs, worktree.as_deref())?
+            }
+            EnvAction::Unset { names, worktree } => {
+                commands::env::run_unset(&names, worktree.as_deref())?
+            }
+            EnvAction::List { worktree } => commands::env::run_list(worktree.as_deref())?,
+            EnvAction::Activate { shell } => commands::env::run_activate(shell)?,
+        },
+        Command::Du { sort } => commands::du::run(sort)?,
+        Command::Remove {
+            names,
+            force,
+            delete_branch,
+        } => commands::remove::run(&names, force, delete_branch)?,
+        Command::Gc { force } => commands::gc::run(force)?,
+        Command::Trash => commands::trash::run()?,
+        Command::Restore { name } => commands::restore::run(&name)?,
+        Command::Prune {
+            force,
+            merged,
+            gone,
+            older_than,
+            clean_only,
+            jobs,
+        } => commands::prune::run(
+            force,
+            commands::prune::PruneCriteria {
+                merged,
+                gone,
+                older_than,
+                clean_only,
+            },
+            jobs,
+        )?,
     }
 
     Ok(())
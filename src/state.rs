@@ -1,49 +1,190 @@
 use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::dry_run;
 use crate::git::{GitError, RepoContext};
 
 const STATE_FILE_NAME: &str = "state";
 
-/// Read the previous worktree name from the state file
-pub fn read_previous_worktree(ctx: &RepoContext) -> Result<Option<String>, GitError> {
-    let state_path = ctx.state_dir().join(STATE_FILE_NAME);
+/// Maximum number of entries kept in the switch history.
+pub const HISTORY_LIMIT: usize = 50;
 
-    if !state_path.exists() {
-        return Ok(None);
+/// A worktree that was switched away from, most recent first in `State::history`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub name: String,
+    /// Unix timestamp (seconds) of when the worktree was left. `0` when unknown,
+    /// e.g. an entry migrated from the legacy single `previous=` line.
+    pub timestamp: u64,
+}
+
+/// Parsed contents of the state file.
+///
+/// The file is line-based `key=value`. History is stored as
+/// `history=<timestamp> <name>` lines, most recent first, and the most recent
+/// entry is also mirrored as `previous=<name>` so older `wt` builds keep
+/// resolving `wt switch -`. Lines this version does not understand are kept
+/// verbatim so a newer `wt` never loses data written by an older one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    pub history: Vec<HistoryEntry>,
+    other: Vec<String>,
+}
+
+impl State {
+    /// Record that `name` was left at `timestamp`: move it to the front of the
+    /// history (each worktree appears at most once) and trim to `HISTORY_LIMIT`.
+    pub fn push(&mut self, name: &str, timestamp: u64) {
+        self.forget(name);
+        self.history.insert(
+            0,
+            HistoryEntry {
+                name: name.to_string(),
+                timestamp,
+            },
+        );
+        self.history.truncate(HISTORY_LIMIT);
     }
 
-    let content = fs::read_to_string(&state_path)
-        .map_err(|e| GitError::new(format!("Failed to read state file: {}", e)))?;
+    /// Drop every history entry for `name`. Returns true if anything was removed.
+    pub fn forget(&mut self, name: &str) -> bool {
+        let before = self.history.len();
+        self.history.retain(|e| e.name != name);
+        self.history.len() != before
+    }
+
+    /// Drop history entries whose name fails `keep`. Returns the dropped names.
+    pub fn retain_history(&mut self, keep: impl Fn(&str) -> bool) -> Vec<String> {
+        let (kept, dropped) = std::mem::take(&mut self.history)
+            .into_iter()
+            .partition(|e| keep(&e.name));
+        self.history = kept;
+        dropped.into_iter().map(|e: HistoryEntry| e.name).collect()
+    }
+
+    /// The `n`-th most recent entry, 1-based (`1` is what `wt switch -` uses).
+    pub fn nth_previous(&self, n: usize) -> Option<&HistoryEntry> {
+        n.checked_sub(1).and_then(|i| self.history.get(i))
+    }
+}
+
+/// Current time as a Unix timestamp in seconds.
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn parse_state(content: &str) -> State {
+    let mut state = State::default();
+    let mut legacy_previous: Option<String> = None;
 
     for line in content.lines() {
-        if let Some(value) = line.strip_prefix("previous=") {
+        if let Some(value) = line.strip_prefix("history=") {
+            let (ts, name) = value.trim().split_once(' ').unwrap_or(("0", value));
+            let name = name.trim();
+            if !name.is_empty() && !state.history.iter().any(|e| e.name == name) {
+                state.history.push(HistoryEntry {
+                    name: name.to_string(),
+                    timestamp: ts.parse().unwrap_or(0),
+                });
+            }
+        } else if let Some(value) = line.strip_prefix("previous=") {
             let value = value.trim();
             if !value.is_empty() {
-                return Ok(Some(value.to_string()));
+                legacy_previous = Some(value.to_string());
             }
+        } else if !line.trim().is_empty() {
+            state.other.push(line.to_string());
+        }
+    }
+
+    // A state file written before history existed only has `previous=`.
+    if state.history.is_empty() {
+        if let Some(name) = legacy_previous {
+            state.history.push(HistoryEntry { name, timestamp: 0 });
         }
     }
 
-    Ok(None)
+    state.history.truncate(HISTORY_LIMIT);
+    state
 }
 
-/// Save the current worktree name as the previous worktree in the state file
-pub fn save_previous_worktree(ctx: &RepoContext, name: &str) -> Result<(), GitError> {
+fn format_state(state: &State) -> String {
+    let mut content = String::new();
+    if let Some(first) = state.history.first() {
+        content.push_str(&format!("previous={}\n", first.name));
+    }
+    for entry in &state.history {
+        content.push_str(&format!("history={} {}\n", entry.timestamp, entry.name));
+    }
+    for line in &state.other {
+        content.push_str(line);
+        content.push('\n');
+    }
+    content
+}
+
+/// Load the state file. A missing file is an empty state.
+pub fn load_state(ctx: &RepoContext) -> Result<State, GitError> {
+    let state_path = ctx.state_dir().join(STATE_FILE_NAME);
+
+    if !state_path.exists() {
+        return Ok(State::default());
+    }
+
+    let content = fs::read_to_string(&state_path)
+        .map_err(|e| GitError::new(format!("Failed to read state file: {}", e)))?;
+
+    Ok(parse_state(&content))
+}
+
+/// Write the state file, creating the state directory if needed.
+pub fn save_state(ctx: &RepoContext, state: &State) -> Result<(), GitError> {
     let state_dir = ctx.state_dir();
     let state_path = state_dir.join(STATE_FILE_NAME);
 
     // Ensure the state directory exists
-    fs::create_dir_all(&state_dir)
+    dry_run::create_dir_all(&state_dir)
         .map_err(|e| GitError::new(format!("Failed to create state directory: {}", e)))?;
 
-    let content = format!("previous={}\n", name);
-
-    fs::write(&state_path, content)
+    dry_run::write_file(&state_path, &format_state(state))
         .map_err(|e| GitError::new(format!("Failed to write state file: {}", e)))?;
 
     Ok(())
 }
 
+/// Read the `n`-th most recent worktree (1-based) from the switch history
+pub fn read_history_entry(ctx: &RepoContext, n: usize) -> Result<Option<String>, GitError> {
+    Ok(load_state(ctx)?.nth_previous(n).map(|e| e.name.clone()))
+}
+
+/// Record a switch from `from` (if we were in a worktree) to `to`.
+///
+/// `from` is pushed onto the history and `to` is dropped from it, so the history
+/// only lists places you can go back to, never the worktree you are standing in.
+pub fn record_switch(ctx: &RepoContext, from: Option<&str>, to: &str) -> Result<(), GitError> {
+    let mut state = load_state(ctx)?;
+    state.forget(to);
+    if let Some(from) = from {
+        if from != to {
+            state.push(from, now_timestamp());
+        }
+    }
+    save_state(ctx, &state)
+}
+
+/// Drop every history entry for a removed worktree so `wt switch -` never
+/// points at a deleted worktree. Leaves the file untouched when nothing matches.
+pub fn forget_worktree(ctx: &RepoContext, name: &str) -> Result<(), GitError> {
+    let mut state = load_state(ctx)?;
+    if state.forget(name) {
+        save_state(ctx, &state)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,6 +200,13 @@ mod tests {
         }
     }
 
+    /// Record leaving `name` at `timestamp` through the state file.
+    fn leave(ctx: &RepoContext, name: &str, timestamp: u64) {
+        let mut state = load_state(ctx).unwrap();
+        state.push(name, timestamp);
+        save_state(ctx, &state).unwrap();
+    }
+
     fn setup_hub_root() -> TempDir {
         let temp_dir = TempDir::new().unwrap();
         fs::create_dir(temp_dir.path().join(".wtree")).unwrap();
@@ -66,97 +214,113 @@ mod tests {
     }
 
     #[test]
-    fn test_read_previous_worktree_no_state_file() {
+    fn test_retain_history_drops_missing() {
+        let mut state = State::default();
+        state.push("a", 1);
+        state.push("gone", 2);
+        state.push("b", 3);
+        let dropped = state.retain_history(|name| name != "gone");
+        assert_eq!(dropped, vec!["gone".to_string()]);
+        let names: Vec<&str> = state.history.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_read_history_entry_no_state_file() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
-        let result = read_previous_worktree(&ctx).unwrap();
+        let result = read_history_entry(&ctx, 1).unwrap();
         assert!(result.is_none());
     }
 
     #[test]
-    fn test_read_previous_worktree_with_value() {
+    fn test_read_history_entry_with_value() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
         let state_dir = hub_root.path().join(".wtree");
         fs::create_dir_all(&state_dir).unwrap();
         fs::write(state_dir.join("state"), "previous=main\n").unwrap();
 
-        let result = read_previous_worktree(&ctx).unwrap();
+        let result = read_history_entry(&ctx, 1).unwrap();
         assert_eq!(result, Some("main".to_string()));
     }
 
     #[test]
-    fn test_read_previous_worktree_empty_value() {
+    fn test_read_history_entry_empty_value() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
         let state_dir = hub_root.path().join(".wtree");
         fs::create_dir_all(&state_dir).unwrap();
         fs::write(state_dir.join("state"), "previous=\n").unwrap();
 
-        let result = read_previous_worktree(&ctx).unwrap();
+        let result = read_history_entry(&ctx, 1).unwrap();
         assert!(result.is_none());
     }
 
     #[test]
-    fn test_read_previous_worktree_whitespace_value() {
+    fn test_read_history_entry_whitespace_value() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
         let state_dir = hub_root.path().join(".wtree");
         fs::create_dir_all(&state_dir).unwrap();
         fs::write(state_dir.join("state"), "previous=   \n").unwrap();
 
-        let result = read_previous_worktree(&ctx).unwrap();
+        let result = read_history_entry(&ctx, 1).unwrap();
         assert!(result.is_none());
     }
 
     #[test]
-    fn test_read_previous_worktree_no_previous_line() {
+    fn test_read_history_entry_no_previous_line() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
         let state_dir = hub_root.path().join(".wtree");
         fs::create_dir_all(&state_dir).unwrap();
         fs::write(state_dir.join("state"), "other=value\n").unwrap();
 
-        let result = read_previous_worktree(&ctx).unwrap();
+        let result = read_history_entry(&ctx, 1).unwrap();
         assert!(result.is_none());
     }
 
     #[test]
-    fn test_save_previous_worktree() {
+    fn test_save_state() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
 
-        save_previous_worktree(&ctx, "feature").unwrap();
+        leave(&ctx, "feature", 100);
 
         let state_path = hub_root.path().join(".wtree/state");
         let content = fs::read_to_string(&state_path).unwrap();
-        assert_eq!(content, "previous=feature\n");
+        assert_eq!(content, "previous=feature\nhistory=100 feature\n");
     }
 
     #[test]
-    fn test_save_previous_worktree_overwrites() {
+    fn test_save_state_migrates_legacy_previous() {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
         let state_dir = hub_root.path().join(".wtree");
         fs::create_dir_all(&state_dir).unwrap();
         fs::write(state_dir.join("state"), "previous=old\n").unwrap();
 
-        save_previous_worktree(&ctx, "new").unwrap();
+        leave(&ctx, "new", 200);
 
         let content = fs::read_to_string(state_dir.join("state")).unwrap();
-        assert_eq!(content, "previous=new\n");
+        assert_eq!(content, "previous=new\nhistory=200 new\nhistory=0 old\n");
+        assert_eq!(
+            read_history_entry(&ctx, 2).unwrap(),
+            Some("old".to_string())
+        );
     }
 
     #[test]
-    fn test_save_previous_worktree_creates_state_dir() {
+    fn test_save_state_creates_state_dir() {
         // No pre-existing state dir: save must create it (lazy state in standard mode).
         let temp_dir = TempDir::new().unwrap();
         let ctx = bare_ctx(temp_dir.path().to_path_buf());
 
-        save_previous_worktree(&ctx, "feature").unwrap();
+        leave(&ctx, "feature", 100);
 
         let content = fs::read_to_string(temp_dir.path().join(".wtree/state")).unwrap();
-        assert_eq!(content, "previous=feature\n");
+        assert_eq!(content, "previous=feature\nhistory=100 feature\n");
     }
 
     #[test]
@@ -172,10 +336,10 @@ mod tests {
             },
         };
 
-        save_previous_worktree(&ctx, "feat").unwrap();
+        leave(&ctx, "feat", 100);
 
         let content = fs::read_to_string(common_dir.join("wtree/state")).unwrap();
-        assert_eq!(content, "previous=feat\n");
+        assert_eq!(content, "previous=feat\nhistory=100 feat\n");
     }
 
     #[test]
@@ -183,9 +347,102 @@ mod tests {
         let hub_root = setup_hub_root();
         let ctx = bare_ctx(hub_root.path().to_path_buf());
 
-        save_previous_worktree(&ctx, "my-worktree").unwrap();
-        let result = read_previous_worktree(&ctx).unwrap();
+        leave(&ctx, "my-worktree", 100);
+        let result = read_history_entry(&ctx, 1).unwrap();
 
         assert_eq!(result, Some("my-worktree".to_string()));
     }
+
+    #[test]
+    fn test_history_is_most_recent_first_and_deduplicated() {
+        let hub_root = setup_hub_root();
+        let ctx = bare_ctx(hub_root.path().to_path_buf());
+
+        leave(&ctx, "a", 1);
+        leave(&ctx, "b", 2);
+        leave(&ctx, "a", 3);
+
+        let names: Vec<String> = load_state(&ctx)
+            .unwrap()
+            .history
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+        let content = fs::read_to_string(hub_root.path().join(".wtree/state")).unwrap();
+        assert_eq!(content, "previous=a\nhistory=3 a\nhistory=2 b\n");
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut state = State::default();
+        for i in 0..HISTORY_LIMIT + 10 {
+            state.push(&format!("wt-{}", i), i as u64);
+        }
+        assert_eq!(state.history.len(), HISTORY_LIMIT);
+        assert_eq!(
+            state.nth_previous(1).unwrap().name,
+            format!("wt-{}", HISTORY_LIMIT + 9)
+        );
+    }
+
+    #[test]
+    fn test_nth_previous_out_of_range() {
+        let mut state = State::default();
+        state.push("a", 1);
+        assert!(state.nth_previous(0).is_none());
+        assert!(state.nth_previous(2).is_none());
+    }
+
+    #[test]
+    fn test_record_switch_drops_target_and_pushes_source() {
+        let hub_root = setup_hub_root();
+        let ctx = bare_ctx(hub_root.path().to_path_buf());
+
+        record_switch(&ctx, Some("a"), "b").unwrap();
+        record_switch(&ctx, Some("b"), "a").unwrap();
+
+        // Toggling back and forth keeps only the worktree we just left.
+        let history = load_state(&ctx).unwrap().history;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].name, "b");
+    }
+
+    #[test]
+    fn test_forget_worktree_cleans_history() {
+        let hub_root = setup_hub_root();
+        let ctx = bare_ctx(hub_root.path().to_path_buf());
+
+        leave(&ctx, "gone", 1);
+        leave(&ctx, "kept", 2);
+        leave(&ctx, "gone", 3);
+        forget_worktree(&ctx, "gone").unwrap();
+
+        assert_eq!(
+            read_history_entry(&ctx, 1).unwrap(),
+            Some("kept".to_string())
+        );
+        assert!(read_history_entry(&ctx, 2).unwrap().is_none());
+        let content = fs::read_to_string(hub_root.path().join(".wtree/state")).unwrap();
+        assert_eq!(content, "previous=kept\nhistory=2 kept\n");
+    }
+
+    #[test]
+    fn test_parse_legacy_previous_only() {
+        let state = parse_state("previous=main\n");
+        assert_eq!(
+            state.history,
+            vec![HistoryEntry {
+                name: "main".to_string(),
+                timestamp: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_format_preserves_unknown_lines() {
+        let content = "previous=a\nhistory=10 a\nfuture=value\n";
+        let state = parse_state(content);
+        assert_eq!(format_state(&state), content);
+    }
 }
@@ -0,0 +1,283 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::git::{
+    get_default_branch, get_repo_default_branch, local_branch_exists, run_git_in_dir, RepoContext,
+};
+
+/// Working-tree and branch status of a single worktree, as shown by
+/// `wt list --status`. Counts come from `git status --porcelain=v2`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WorktreeStatus {
+    /// Paths with changes in the index.
+    pub staged: usize,
+    /// Tracked paths with unstaged changes in the working tree.
+    pub modified: usize,
+    pub untracked: usize,
+    /// Paths with unresolved merge conflicts.
+    pub conflicted: usize,
+    /// Upstream branch (e.g. `origin/feature`), if one is configured.
+    pub upstream: Option<String>,
+    /// True when an upstream is configured but no longer exists on the remote.
+    pub upstream_gone: bool,
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    /// Commits ahead of / behind the repository's default branch.
+    pub default_ahead: Option<usize>,
+    pub default_behind: Option<usize>,
+    /// Unix timestamp of the HEAD commit.
+    pub last_commit_time: Option<u64>,
+    pub last_commit_subject: Option<String>,
+}
+
+impl WorktreeStatus {
+    /// True when there is anything not yet committed (staged, unstaged,
+    /// untracked, or conflicted).
+    pub fn is_dirty(&self) -> bool {
+        self.staged + self.modified + self.untracked + self.conflicted > 0
+    }
+}
+
+/// Parse `git status --porcelain=v2 --branch` output into the change counts and
+/// upstream tracking fields of a `WorktreeStatus`.
+pub fn parse_status_v2(output: &str) -> WorktreeStatus {
+    let mut status = WorktreeStatus::default();
+    let mut has_ab = false;
+
+    for line in output.lines() {
+        if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream = Some(upstream.to_string());
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            has_ab = true;
+            for part in ab.split_whitespace() {
+                if let Some(n) = part.strip_prefix('+') {
+                    status.ahead = n.parse().ok();
+                } else if let Some(n) = part.strip_prefix('-') {
+                    status.behind = n.parse().ok();
+                }
+            }
+        } else if line.starts_with("1 ") || line.starts_with("2 ") {
+            // `<type> <XY> ...`: X is the index status, Y the worktree status.
+            let mut xy = line[2..].chars();
+            if xy.next().is_some_and(|x| x != '.') {
+                status.staged += 1;
+            }
+            if xy.next().is_some_and(|y| y != '.') {
+                status.modified += 1;
+            }
+        } else if line.starts_with("u ") {
+            status.conflicted += 1;
+        } else if line.starts_with("? ") {
+            status.untracked += 1;
+        }
+    }
+
+    // git omits `branch.ab` when the configured upstream ref no longer exists.
+    status.upstream_gone = status.upstream.is_some() && !has_ab;
+    status
+}
+
+/// Parse `git rev-list --left-right --count <base>...HEAD` output
+/// (`<behind>\t<ahead>`) into `(ahead, behind)`.
+pub fn parse_left_right(output: &str) -> Option<(usize, usize)> {
+    let mut parts = output.split_whitespace();
+    let behind = parts.next()?.parse().ok()?;
+    let ahead = parts.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
+
+/// The ref the default-branch ahead/behind counts compare against: the local
+/// default branch if it exists, otherwise its `origin/` remote-tracking ref. A
+/// repository with no remote default falls back to a local `main` or `master`,
+/// then to the branch checked out in the main worktree.
+pub fn default_compare_ref(ctx: &RepoContext) -> Option<String> {
+    let branch = get_repo_default_branch(ctx)
+        .or_else(|| {
+            ["main", "master"]
+                .into_iter()
+                .find(|b| local_branch_exists(ctx.anchor_dir(), b))
+                .map(String::from)
+        })
+        .or_else(|| get_default_branch(ctx.main_worktree()?))?;
+    if local_branch_exists(ctx.anchor_dir(), &branch) {
+        Some(format!("refs/heads/{}", branch))
+    } else {
+        Some(format!("refs/remotes/origin/{}", branch))
+    }
+}
+
+/// Collect the status of the worktree at `path`. `default_ref` is the ref from
+/// `default_compare_ref`. Fails only when `git status` itself fails (e.g. the
+/// worktree directory is gone); the optional parts are left `None` on error.
+pub fn collect_status(path: &Path, default_ref: Option<&str>) -> Option<WorktreeStatus> {
+    let output = run_git_in_dir(path, &["status", "--porcelain=v2", "--branch"]).ok()?;
+    let mut status = parse_status_v2(&output);
+
+    if let Some(default_ref) = default_ref {
+        let range = format!("{}...HEAD", default_ref);
+        if let Some((ahead, behind)) =
+            run_git_in_dir(path, &["rev-list", "--left-right", "--count", &range])
+                .ok()
+                .and_then(|out| parse_left_right(&out))
+        {
+            status.default_ahead = Some(ahead);
+            status.default_behind = Some(behind);
+        }
+    }
+
+    if let Ok(out) = run_git_in_dir(path, &["log", "-1", "--format=%ct %s"]) {
+        let (time, subject) = out.split_once(' ').unwrap_or((out.as_str(), ""));
+        status.last_commit_time = time.parse().ok();
+        status.last_commit_subject = Some(subject.to_string());
+    }
+
+    Some(status)
+}
+
+/// Commits in the worktree at `path` that exist nowhere else: ahead of the
+/// upstream when one is tracked, otherwise not reachable from any remote-tracking
+/// ref nor from `default_ref` (so a fresh branch off the default branch counts 0).
+pub fn count_unpushed(path: &Path, status: &WorktreeStatus, default_ref: Option<&str>) -> usize {
+    if status.upstream.is_some() && !status.upstream_gone {
+        return status.ahead.unwrap_or(0);
+    }
+    let mut args = vec!["rev-list", "--count", "HEAD", "--not", "--remotes"];
+    if let Some(default_ref) = default_ref {
+        args.push(default_ref);
+    }
+    run_git_in_dir(path, &args)
+        .ok()
+        .and_then(|out| out.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Describe work that removing the worktree at `path` would lose, or `None`
+/// when it is clean and fully pushed.
+pub fn describe_work_at_risk(path: &Path, default_ref: Option<&str>) -> Option<String> {
+    let status = collect_status(path, default_ref)?;
+    let unpushed = count_unpushed(path, &status, default_ref);
+
+    let mut parts = Vec::new();
+    if status.is_dirty() {
+        let counts: Vec<String> = [
+            ("staged", status.staged),
+            ("modified", status.modified),
+            ("untracked", status.untracked),
+            ("conflicted", status.conflicted),
+        ]
+        .iter()
+        .filter(|(_, n)| *n > 0)
+        .map(|(label, n)| format!("{} {}", n, label))
+        .collect();
+        parts.push(format!("uncommitted changes ({})", counts.join(", ")));
+    }
+    if unpushed > 0 {
+        parts.push(format!("{} unpushed commit(s)", unpushed));
+    }
+
+    (!parts.is_empty()).then(|| parts.join(" and "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Layout;
+    use crate::test_util::{git, init_repo};
+    use std::fs;
+
+    #[test]
+    fn test_parse_status_v2_counts() {
+        let output = "\
+# branch.oid abc123
+# branch.head feat
+# branch.upstream origin/feat
+# branch.ab +2 -1
+1 M. N... 100644 100644 100644 abc abc staged.rs
+1 .M N... 100644 100644 100644 abc abc modified.rs
+1 MM N... 100644 100644 100644 abc abc both.rs
+2 R. N... 100644 100644 100644 abc abc R100 new.rs\told.rs
+u UU N... 100644 100644 100644 100644 abc abc abc conflict.rs
+? untracked.txt
+? other.txt
+";
+        let status = parse_status_v2(output);
+        assert_eq!(status.staged, 3);
+        assert_eq!(status.modified, 2);
+        assert_eq!(status.untracked, 2);
+        assert_eq!(status.conflicted, 1);
+        assert_eq!(status.upstream.as_deref(), Some("origin/feat"));
+        assert_eq!(status.ahead, Some(2));
+        assert_eq!(status.behind, Some(1));
+        assert!(!status.upstream_gone);
+        assert!(status.is_dirty());
+    }
+
+    #[test]
+    fn test_parse_status_v2_clean_no_upstream() {
+        let status = parse_status_v2("# branch.oid abc123\n# branch.head main\n");
+        assert!(!status.is_dirty());
+        assert!(status.upstream.is_none());
+        assert!(status.ahead.is_none());
+        assert!(!status.upstream_gone);
+    }
+
+    #[test]
+    fn test_parse_status_v2_upstream_gone() {
+        let status = parse_status_v2("# branch.head feat\n# branch.upstream origin/feat\n");
+        assert!(status.upstream_gone);
+        assert!(status.ahead.is_none());
+    }
+
+    #[test]
+    fn test_describe_work_at_risk() {
+        let tmp = init_repo();
+        let root = tmp.path();
+        git(root, &["checkout", "-q", "-b", "feat"]);
+
+        // Fresh branch off the default branch: nothing at risk.
+        assert!(describe_work_at_risk(root, Some("refs/heads/main")).is_none());
+
+        git(root, &["commit", "--allow-empty", "-q", "-m", "work"]);
+        fs::write(root.join("new.txt"), "x").unwrap();
+        let risk = describe_work_at_risk(root, Some("refs/heads/main")).unwrap();
+        assert_eq!(
+            risk,
+            "uncommitted changes (1 untracked) and 1 unpushed commit(s)"
+        );
+    }
+
+    #[test]
+    fn test_default_compare_ref_without_remote() {
+        let tmp = init_repo();
+        let root = tmp.path().to_path_buf();
+        let ctx = RepoContext {
+            layout: Layout::Standard {
+                main_worktree: root.clone(),
+                common_dir: root.join(".git"),
+            },
+        };
+        let default_ref = default_compare_ref(&ctx);
+        assert_eq!(default_ref.as_deref(), Some("refs/heads/main"));
+
+        // A fresh branch in a repo with no remote has nothing unpushed
+        git(&root, &["checkout", "-q", "-b", "feat"]);
+        assert!(describe_work_at_risk(&root, default_ref.as_deref()).is_none());
+
+        // With neither main nor master, the main worktree's branch is used
+        git(&root, &["branch", "-q", "-m", "main", "trunk"]);
+        git(&root, &["checkout", "-q", "trunk"]);
+        assert_eq!(
+            default_compare_ref(&ctx).as_deref(),
+            Some("refs/heads/trunk")
+        );
+    }
+
+    #[test]
+    fn test_parse_left_right() {
+        assert_eq!(parse_left_right("3\t5"), Some((5, 3)));
+        assert_eq!(parse_left_right("0\t0\n"), Some((0, 0)));
+        assert_eq!(parse_left_right(""), None);
+        assert_eq!(parse_left_right("x\t1"), None);
+    }
+}
@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests that need a real git repository.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use tempfile::TempDir;
+
+/// Run git quietly in `dir` with a fixed identity, panicking on failure.
+pub fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.email=t@t", "-c", "user.name=t"])
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .expect("failed to spawn git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// A fresh repository on branch `main` with one empty commit.
+pub fn init_repo() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    git(tmp.path(), &["init", "-q", "-b", "main"]);
+    git(tmp.path(), &["commit", "--allow-empty", "-q", "-m", "init"]);
+    tmp
+}
@@ -0,0 +1,358 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::load_config;
+use crate::dry_run;
+use crate::git::{run_git_in_dir, run_git_mut, GitError, RepoContext};
+
+/// Ref namespace holding one snapshot commit per removed worktree.
+pub const TRASH_PREFIX: &str = "refs/wtree/trash/";
+
+/// Days a trash entry is kept when `trash_days` is not configured.
+pub const DEFAULT_TRASH_DAYS: u64 = 14;
+
+/// A removed worktree kept under `refs/wtree/trash/<name>`.
+///
+/// The snapshot commit's tree is the worktree's files at removal time
+/// (tracked and untracked, not ignored) and its parent is the HEAD the worktree
+/// was at, so the branch tip survives even if the branch is deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashEntry {
+    pub name: String,
+    pub commit: String,
+    pub head: String,
+    pub branch: Option<String>,
+    pub path: PathBuf,
+    pub timestamp: u64,
+}
+
+/// Retention in seconds, or `None` when the trash is disabled (`trash_days = 0`).
+fn retention(ctx: &RepoContext) -> Option<u64> {
+    match load_config(ctx).trash_days.unwrap_or(DEFAULT_TRASH_DAYS) {
+        0 => None,
+        // An absurd `trash_days` just means "keep forever"
+        days => Some(days.saturating_mul(86_400)),
+    }
+}
+
+/// Commit message of a snapshot; `parse_entries` reads the fields back.
+fn format_message(name: &str, branch: Option<&str>, head: &str, path: &Path) -> String {
+    format!(
+        "wtree trash: {}\n\nbranch: {}\nhead: {}\npath: {}\n",
+        name,
+        branch.unwrap_or(""),
+        head,
+        path.display()
+    )
+}
+
+/// Format string for `git for-each-ref`: unit-separated fields, one record per
+/// record separator (the message spans several lines).
+const FOR_EACH_REF_FORMAT: &str =
+    "%(refname)%1f%(objectname)%1f%(committerdate:unix)%1f%(contents)%1e";
+
+/// Parse `git for-each-ref --format=FOR_EACH_REF_FORMAT` output.
+fn parse_entries(output: &str) -> Vec<TrashEntry> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            let name = fields.next()?.strip_prefix(TRASH_PREFIX)?.to_string();
+            let commit = fields.next()?.to_string();
+            let timestamp = fields.next()?.parse().ok()?;
+            let message = fields.next()?;
+
+            let field = |key: &str| {
+                message
+                    .lines()
+                    .find_map(|l| l.strip_prefix(key))
+                    .map(|v| v.trim().to_string())
+            };
+            Some(TrashEntry {
+                name,
+                commit,
+                head: field("head:")?,
+                branch: field("branch:").filter(|b| !b.is_empty()),
+                path: PathBuf::from(field("path:")?),
+                timestamp,
+            })
+        })
+        .collect()
+}
+
+/// Entries older than `retention` seconds at `now`.
+fn expired(entries: &[TrashEntry], retention: u64, now: u64) -> Vec<&TrashEntry> {
+    entries
+        .iter()
+        .filter(|e| now.saturating_sub(e.timestamp) >= retention)
+        .collect()
+}
+
+/// List the trash, most recently removed first.
+pub fn list_trash(anchor: &Path) -> Result<Vec<TrashEntry>, GitError> {
+    let format = format!("--format={}", FOR_EACH_REF_FORMAT);
+    let output = run_git_in_dir(anchor, &["for-each-ref", &format, TRASH_PREFIX])?;
+    let mut entries = parse_entries(&output);
+    entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    Ok(entries)
+}
+
+/// Run git in `dir` with extra environment variables.
+fn run_git_with_env(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String, GitError> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .envs(env.iter().copied())
+        .output()
+        .map_err(|e| GitError::new(format!("Failed to execute git: {}", e)))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(GitError::new(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// A worktree's files committed for the trash, not yet filed under its ref.
+///
+/// `file` adds the entry once the worktree is really gone, so a removal that
+/// fails leaves no trash entry for a worktree that still exists (only an
+/// unreferenced commit, which git collects in time).
+#[derive(Debug)]
+pub struct Snapshot {
+    name: String,
+    commit: String,
+}
+
+impl Snapshot {
+    /// Add the snapshot to the trash as `refs/wtree/trash/<name>`.
+    pub fn file(&self, anchor: &Path) -> Result<(), GitError> {
+        let refname = format!("{}{}", TRASH_PREFIX, self.name);
+        run_git_in_dir(anchor, &["update-ref", &refname, &self.commit])?;
+        Ok(())
+    }
+}
+
+/// Snapshot the worktree at `path` before it is removed.
+///
+/// The working tree is staged into a throwaway index, so the worktree's own
+/// index is untouched. Returns `None` when the trash is disabled, the directory
+/// is already gone (nothing left to lose) or in a dry run.
+pub fn snapshot(
+    ctx: &RepoContext,
+    name: &str,
+    path: &Path,
+    head: &str,
+    branch: Option<&str>,
+) -> Result<Option<Snapshot>, GitError> {
+    if retention(ctx).is_none() || !path.is_dir() {
+        return Ok(None);
+    }
+    if dry_run::is_enabled() {
+        dry_run::report(&format!("save '{}' to {}{}", name, TRASH_PREFIX, name));
+        return Ok(None);
+    }
+
+    let index = run_git_in_dir(path, &["rev-parse", "--git-path", "wtree-trash-index"])?;
+    let index = path.join(index);
+    let index_str = index.to_string_lossy();
+    let env = [
+        ("GIT_INDEX_FILE", index_str.as_ref()),
+        ("GIT_AUTHOR_NAME", "wtree"),
+        ("GIT_AUTHOR_EMAIL", "wtree@localhost"),
+        ("GIT_COMMITTER_NAME", "wtree"),
+        ("GIT_COMMITTER_EMAIL", "wtree@localhost"),
+    ];
+    let commit = (|| {
+        run_git_with_env(path, &["read-tree", head], &env)?;
+        run_git_with_env(path, &["add", "-A", "."], &env)?;
+        let tree = run_git_with_env(path, &["write-tree"], &env)?;
+        let message = format_message(name, branch, head, path);
+        run_git_with_env(
+            path,
+            &["commit-tree", &tree, "-p", head, "-m", &message],
+            &env,
+        )
+    })();
+    let _ = std::fs::remove_file(&index);
+
+    Ok(Some(Snapshot {
+        name: name.to_string(),
+        commit: commit?,
+    }))
+}
+
+/// Put a snapshot's files back into the worktree at `path` without staging
+/// them: modified and deleted files come back as changes, files that were
+/// untracked stay untracked.
+pub fn restore_files(path: &Path, commit: &str) -> Result<(), GitError> {
+    run_git_in_dir(
+        path,
+        &["restore", "--source", commit, "--worktree", "--", "."],
+    )?;
+    Ok(())
+}
+
+/// Drop one trash entry.
+pub fn forget(anchor: &Path, name: &str) -> Result<(), GitError> {
+    run_git_mut(
+        anchor,
+        &["update-ref", "-d", &format!("{}{}", TRASH_PREFIX, name)],
+    )?;
+    Ok(())
+}
+
+/// Drop entries older than the configured retention. Returns how many went.
+pub fn expire(ctx: &RepoContext, now: u64) -> Result<usize, GitError> {
+    let Some(retention) = retention(ctx) else {
+        return Ok(0);
+    };
+    let anchor = ctx.anchor_dir();
+    let entries = list_trash(anchor)?;
+    let old = expired(&entries, retention, now);
+    for entry in &old {
+        forget(anchor, &entry.name)?;
+    }
+    Ok(old.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Layout;
+    use crate::test_util::{git, init_repo};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_retention_saturates() {
+        let tmp = TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join(".wtree")).unwrap();
+        let ctx = RepoContext {
+            layout: Layout::Bare {
+                hub_root: tmp.path().to_path_buf(),
+            },
+        };
+        let set = |days: u64| {
+            fs::write(
+                tmp.path().join(".wtree/config.toml"),
+                format!("trash_days = {}\n", days),
+            )
+            .unwrap()
+        };
+        set(2);
+        assert_eq!(retention(&ctx), Some(2 * 86_400));
+        set(0);
+        assert_eq!(retention(&ctx), None);
+        set(i64::MAX as u64);
+        assert_eq!(retention(&ctx), Some(u64::MAX));
+    }
+
+    fn record(name: &str, ts: u64, message: &str) -> String {
+        format!(
+            "{}{}\x1fabc123\x1f{}\x1f{}\x1e\n",
+            TRASH_PREFIX, name, ts, message
+        )
+    }
+
+    #[test]
+    fn test_parse_entries_roundtrip() {
+        let message = format_message("feat", Some("feature/x"), "def456", Path::new("/hub/feat"));
+        let entries = parse_entries(&record("feat", 100, &message));
+        assert_eq!(
+            entries,
+            vec![TrashEntry {
+                name: "feat".to_string(),
+                commit: "abc123".to_string(),
+                head: "def456".to_string(),
+                branch: Some("feature/x".to_string()),
+                path: PathBuf::from("/hub/feat"),
+                timestamp: 100,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_entries_detached_and_multiple() {
+        let a = format_message("a", None, "111", Path::new("/hub/a"));
+        let b = format_message("b", Some("b"), "222", Path::new("/hub/b"));
+        let output = record("a", 1, &a) + &record("b", 2, &b);
+        let entries = parse_entries(&output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].branch, None);
+        assert_eq!(entries[1].name, "b");
+    }
+
+    #[test]
+    fn test_parse_entries_skips_foreign_refs() {
+        let output = "refs/heads/main\x1fabc\x1f1\x1fmsg\x1e\n";
+        assert!(parse_entries(output).is_empty());
+        assert!(parse_entries("").is_empty());
+    }
+
+    #[test]
+    fn test_expired() {
+        let message = format_message("x", None, "1", Path::new("/x"));
+        let entries = parse_entries(&(record("old", 0, &message) + &record("new", 900, &message)));
+        let old = expired(&entries, 500, 1000);
+        assert_eq!(old.len(), 1);
+        assert_eq!(old[0].name, "old");
+    }
+
+    #[test]
+    fn test_snapshot_remove_and_restore() {
+        let tmp = init_repo();
+        let root = tmp.path().canonicalize().unwrap();
+        fs::write(root.join("a.txt"), "one\n").unwrap();
+        git(&root, &["add", "a.txt"]);
+        git(&root, &["commit", "-q", "-m", "a"]);
+        let outside = TempDir::new().unwrap();
+        let wt = outside.path().canonicalize().unwrap().join("feat");
+        let wt_str = wt.to_string_lossy().to_string();
+        git(&root, &["worktree", "add", "-q", "-b", "feat", &wt_str]);
+
+        fs::write(wt.join("a.txt"), "two\n").unwrap();
+        git(&wt, &["add", "a.txt"]);
+        fs::write(wt.join("new.txt"), "new\n").unwrap();
+        let staged = || run_git_in_dir(&wt, &["diff", "--cached", "--name-only"]).unwrap();
+        assert_eq!(staged(), "a.txt");
+
+        let ctx = RepoContext {
+            layout: Layout::Standard {
+                main_worktree: root.clone(),
+                common_dir: root.join(".git"),
+            },
+        };
+        let head = run_git_in_dir(&wt, &["rev-parse", "HEAD"]).unwrap();
+        let snapshot = snapshot(&ctx, "feat", &wt, &head, Some("feat"))
+            .unwrap()
+            .unwrap();
+        // The worktree's own index is untouched, and nothing is filed yet
+        assert_eq!(staged(), "a.txt");
+        assert!(list_trash(&root).unwrap().is_empty());
+
+        git(&root, &["worktree", "remove", "--force", &wt_str]);
+        snapshot.file(&root).unwrap();
+        let entries = list_trash(&root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].branch.as_deref(), Some("feat"));
+        assert_eq!(entries[0].head, head);
+        assert_eq!(entries[0].path, wt);
+
+        git(&root, &["worktree", "add", "-q", &wt_str, "feat"]);
+        restore_files(&wt, &entries[0].commit).unwrap();
+        assert_eq!(fs::read_to_string(wt.join("a.txt")).unwrap(), "two\n");
+        assert_eq!(fs::read_to_string(wt.join("new.txt")).unwrap(), "new\n");
+        // Back as unstaged changes, the new file still untracked
+        assert_eq!(staged(), "");
+        assert_eq!(
+            run_git_in_dir(&wt, &["diff", "--name-only"]).unwrap(),
+            "a.txt"
+        );
+        assert_eq!(
+            run_git_in_dir(&wt, &["ls-files", "--others", "--exclude-standard"]).unwrap(),
+            "new.txt"
+        );
+    }
+}
@@ -0,0 +1,558 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::WtreeConfig;
+use crate::dry_run;
+use crate::git::RepoContext;
+
+const ENV_DIR_NAME: &str = "env";
+
+/// Variables the shell currently has applied from a worktree, space-separated,
+/// so the next activation knows what to undo.
+pub const KEYS_VAR: &str = "WT_ENV_KEYS";
+
+/// Prefix of the variables that remember a value a worktree variable shadowed.
+const ORIG_PREFIX: &str = "WT_ENV_ORIG_";
+
+/// File hooks append `KEY=VALUE` lines to, to export variables into the worktree.
+pub const EXPORT_FILE_VAR: &str = "WT_ENV_FILE";
+
+/// Counter making each `WT_ENV_FILE` name unique within this process.
+static EXPORT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Where a worktree variable comes from. Later sources win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Config,
+    Hook,
+    Store,
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Config => "config",
+            Source::Hook => "hook",
+            Source::Store => "wt env set",
+        }
+    }
+}
+
+/// A change the shell has to make to its environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Set(String, String),
+    Unset(String),
+}
+
+/// Whether `key` is usable as an environment variable name in every shell.
+pub fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse `KEY=VALUE` lines. Blank lines, `#` comments and invalid keys are
+/// skipped; an `export ` prefix is accepted. A value in single quotes is taken
+/// literally, one in double quotes has `\\`, `\"`, `\n` and `\r` unescaped,
+/// and a bare value is trimmed.
+pub fn parse_env_lines(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if !is_valid_key(key) {
+                return None;
+            }
+            let value = value.trim();
+            let value = if let Some(inner) = quoted(value, '"') {
+                unescape(inner)
+            } else {
+                quoted(value, '\'').unwrap_or(value).to_string()
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+fn quoted(value: &str, quote: char) -> Option<&str> {
+    value.strip_prefix(quote)?.strip_suffix(quote)
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(c @ ('\\' | '"')) => out.push(c),
+            // Unknown escapes stay as written
+            Some(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Values are always written double-quoted and escaped, so any string,
+/// including newlines, quotes and surrounding spaces, reads back unchanged.
+fn format_env_lines(vars: &BTreeMap<String, String>) -> String {
+    vars.iter()
+        .map(|(k, v)| {
+            let escaped = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r");
+            format!("{}=\"{}\"\n", k, escaped)
+        })
+        .collect()
+}
+
+fn store_path(ctx: &RepoContext, name: &str, source: Source) -> PathBuf {
+    let file = match source {
+        Source::Hook => format!("{}.hooks.env", name),
+        _ => format!("{}.env", name),
+    };
+    ctx.state_dir().join(ENV_DIR_NAME).join(file)
+}
+
+/// Worktrees are keyed by directory name, like the switch history.
+pub fn worktree_key(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// The store hooks of the worktree at `path` export into.
+pub fn hook_store_path(ctx: &RepoContext, path: &Path) -> PathBuf {
+    store_path(ctx, &worktree_key(path), Source::Hook)
+}
+
+fn load_file(path: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(path)
+        .map(|c| parse_env_lines(&c).into_iter().collect())
+        .unwrap_or_default()
+}
+
+fn save_file(path: &Path, vars: &BTreeMap<String, String>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        dry_run::create_dir_all(dir)?;
+    }
+    dry_run::write_file(path, &format_env_lines(vars))
+}
+
+/// Variables set for worktree `name` with `wt env set`.
+pub fn load_store(ctx: &RepoContext, name: &str) -> BTreeMap<String, String> {
+    load_file(&store_path(ctx, name, Source::Store))
+}
+
+pub fn save_store(
+    ctx: &RepoContext,
+    name: &str,
+    vars: &BTreeMap<String, String>,
+) -> std::io::Result<()> {
+    save_file(&store_path(ctx, name, Source::Store), vars)
+}
+
+/// Create a fresh, empty `WT_ENV_FILE` next to the store at `store`. The file
+/// must not exist yet and is readable by the owner only, so nobody else can
+/// plant variables in it. Its name does not end in `.env`, so it is never
+/// mistaken for a store.
+pub fn create_export_file(store: &Path) -> std::io::Result<PathBuf> {
+    let dir = store.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        ".export-{}-{}",
+        std::process::id(),
+        EXPORT_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(&path)?;
+    Ok(path)
+}
+
+/// Merge the lines a hook wrote to `WT_ENV_FILE` into the store at `store`.
+pub fn merge_exports(store: &Path, exported: &str) -> std::io::Result<()> {
+    let exports = parse_env_lines(exported);
+    if exports.is_empty() {
+        return Ok(());
+    }
+    let mut vars = load_file(store);
+    vars.extend(exports);
+    save_file(store, &vars)
+}
+
+/// Worktree names that have a stored environment (set or exported by hooks).
+pub fn stored_names(ctx: &RepoContext) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(ctx.state_dir().join(ENV_DIR_NAME)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|e| {
+            let file = e.file_name().to_string_lossy().to_string();
+            let name = file
+                .strip_suffix(".hooks.env")
+                .or_else(|| file.strip_suffix(".env"))?;
+            Some(name.to_string())
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Delete every stored variable of worktree `name`.
+pub fn forget(ctx: &RepoContext, name: &str) -> std::io::Result<()> {
+    for source in [Source::Hook, Source::Store] {
+        let path = store_path(ctx, name, source);
+        if !path.exists() {
+            continue;
+        }
+        if dry_run::is_enabled() {
+            dry_run::report(&format!("rm {}", path.display()));
+        } else {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// All variables for worktree `name` at `path`, with where each came from:
+/// config `[env]` (with `{name}` and `{path}` filled in), then hook exports,
+/// then `wt env set`, later sources overriding earlier ones. Config names no
+/// shell can hold are skipped with a warning: they end up in shell code.
+pub fn resolve(
+    ctx: &RepoContext,
+    config: &WtreeConfig,
+    name: &str,
+    path: &Path,
+) -> BTreeMap<String, (String, Source)> {
+    let mut vars = BTreeMap::new();
+    for (key, value) in &config.env {
+        if !is_valid_key(key) {
+            eprintln!(
+                "Warning: ignoring [env] variable '{}': not a valid variable name",
+                key
+            );
+            continue;
+        }
+        let value = value
+            .replace("{name}", name)
+            .replace("{path}", &path.to_string_lossy());
+        vars.insert(key.clone(), (value, Source::Config));
+    }
+    for source in [Source::Hook, Source::Store] {
+        for (key, value) in load_file(&store_path(ctx, name, source)) {
+            vars.insert(key, (value, source));
+        }
+    }
+    vars
+}
+
+/// Replace `${VAR}` references with values from `lookup` (empty when unset).
+fn expand_vars(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&lookup(&rest[start + 2..start + 2 + len]).unwrap_or_default());
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The changes that take a shell whose environment is `current` from whatever
+/// worktree it had applied to `target`: variables of the previous worktree are
+/// unset (or restored to the value they shadowed), and `target` is applied on
+/// top, with `${VAR}` expanded against the environment as it was before any
+/// worktree variables.
+pub fn plan(
+    current: &dyn Fn(&str) -> Option<String>,
+    target: &BTreeMap<String, String>,
+) -> Vec<Op> {
+    let active: Vec<String> = current(KEYS_VAR)
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let orig = |key: &str| current(&format!("{}{}", ORIG_PREFIX, key));
+    let base = |key: &str| {
+        if active.iter().any(|k| k == key) {
+            orig(key)
+        } else {
+            current(key)
+        }
+    };
+
+    let mut ops = Vec::new();
+    for key in active.iter().filter(|k| !target.contains_key(*k)) {
+        match orig(key) {
+            Some(value) => {
+                ops.push(Op::Set(key.clone(), value));
+                ops.push(Op::Unset(format!("{}{}", ORIG_PREFIX, key)));
+            }
+            None => ops.push(Op::Unset(key.clone())),
+        }
+    }
+    for (key, value) in target {
+        let was_active = active.iter().any(|k| k == key);
+        if !was_active {
+            if let Some(shadowed) = current(key) {
+                ops.push(Op::Set(format!("{}{}", ORIG_PREFIX, key), shadowed));
+            }
+        }
+        ops.push(Op::Set(key.clone(), expand_vars(value, &base)));
+    }
+    if !target.is_empty() {
+        let keys: Vec<&str> = target.keys().map(String::as_str).collect();
+        ops.push(Op::Set(KEYS_VAR.to_string(), keys.join(" ")));
+    } else if !active.is_empty() {
+        ops.push(Op::Unset(KEYS_VAR.to_string()));
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Layout;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_export_file_is_fresh_and_private() {
+        let tmp = TempDir::new().unwrap();
+        let store = tmp.path().join("env/feat.hooks.env");
+        let first = create_export_file(&store).unwrap();
+        let second = create_export_file(&store).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), store.parent());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    fn env_of(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: BTreeMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| map.get(key).cloned()
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn set(k: &str, v: &str) -> Op {
+        Op::Set(k.to_string(), v.to_string())
+    }
+
+    fn unset(k: &str) -> Op {
+        Op::Unset(k.to_string())
+    }
+
+    #[test]
+    fn test_parse_env_lines() {
+        let content =
+            "# comment\nPORT=3001\nexport NAME=\"my app\"\n\nQUOTED='x=y'\n1BAD=no\nnot a line\n";
+        assert_eq!(
+            parse_env_lines(content),
+            vec![
+                ("PORT".to_string(), "3001".to_string()),
+                ("NAME".to_string(), "my app".to_string()),
+                ("QUOTED".to_string(), "x=y".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_env_lines_escapes() {
+        assert_eq!(
+            parse_env_lines("A=\"x\\\"y\\\\z\\n\"\nB=\"C:\\dir\"\nC='a\\nb'\n"),
+            vec![
+                ("A".to_string(), "x\"y\\z\n".to_string()),
+                ("B".to_string(), "C:\\dir".to_string()),
+                ("C".to_string(), "a\\nb".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_store_round_trips_any_value() {
+        let tmp = TempDir::new().unwrap();
+        let store = tmp.path().join("env/feat.env");
+        let original = vars(&[
+            ("MULTI", "a\nb\r\nc"),
+            ("QUOTED", "\"x\""),
+            ("SINGLE", "'y'"),
+            ("SPACES", "  padded  "),
+            ("SLASHES", "C:\\dir\\"),
+            ("EQUALS", "k=v # not a comment"),
+            ("EMPTY", ""),
+        ]);
+        save_file(&store, &original).unwrap();
+        assert_eq!(load_file(&store), original);
+        assert_eq!(
+            fs::read_to_string(&store).unwrap().lines().count(),
+            original.len()
+        );
+    }
+
+    #[test]
+    fn test_is_valid_key() {
+        assert!(is_valid_key("_A1"));
+        assert!(!is_valid_key("1A"));
+        assert!(!is_valid_key("A-B"));
+        assert!(!is_valid_key(""));
+    }
+
+    #[test]
+    fn test_plan_activate_from_clean_shell() {
+        let ops = plan(&env_of(&[]), &vars(&[("PORT", "3001")]));
+        assert_eq!(ops, vec![set("PORT", "3001"), set(KEYS_VAR, "PORT")]);
+    }
+
+    #[test]
+    fn test_plan_saves_shadowed_value_and_expands_it() {
+        let ops = plan(
+            &env_of(&[("PATH", "/usr/bin")]),
+            &vars(&[("PATH", "/wt/.venv/bin:${PATH}")]),
+        );
+        assert_eq!(
+            ops,
+            vec![
+                set("WT_ENV_ORIG_PATH", "/usr/bin"),
+                set("PATH", "/wt/.venv/bin:/usr/bin"),
+                set(KEYS_VAR, "PATH"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_leaving_unsets_and_restores() {
+        let current = env_of(&[
+            (KEYS_VAR, "PORT PATH"),
+            ("PORT", "3001"),
+            ("PATH", "/wt/.venv/bin:/usr/bin"),
+            ("WT_ENV_ORIG_PATH", "/usr/bin"),
+        ]);
+        assert_eq!(
+            plan(&current, &BTreeMap::new()),
+            vec![
+                unset("PORT"),
+                set("PATH", "/usr/bin"),
+                unset("WT_ENV_ORIG_PATH"),
+                unset(KEYS_VAR),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_switching_expands_against_original_value() {
+        let current = env_of(&[
+            (KEYS_VAR, "PATH PORT"),
+            ("PATH", "/a/.venv/bin:/usr/bin"),
+            ("WT_ENV_ORIG_PATH", "/usr/bin"),
+            ("PORT", "3001"),
+        ]);
+        let ops = plan(&current, &vars(&[("PATH", "/b/.venv/bin:${PATH}")]));
+        assert_eq!(
+            ops,
+            vec![
+                unset("PORT"),
+                set("PATH", "/b/.venv/bin:/usr/bin"),
+                set(KEYS_VAR, "PATH"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_nothing_to_do() {
+        assert!(plan(&env_of(&[("HOME", "/h")]), &BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_precedence_and_placeholders() {
+        let tmp = TempDir::new().unwrap();
+        let ctx = RepoContext {
+            layout: Layout::Bare {
+                hub_root: tmp.path().to_path_buf(),
+            },
+        };
+        let env_dir = ctx.state_dir().join(ENV_DIR_NAME);
+        fs::create_dir_all(&env_dir).unwrap();
+        fs::write(env_dir.join("feat.hooks.env"), "PORT=3001\nDB=hook\n").unwrap();
+        fs::write(env_dir.join("feat.env"), "DB=mine\n").unwrap();
+        let config = WtreeConfig {
+            env: vars(&[
+                ("PROJECT", "app-{name}"),
+                ("PORT", "3000"),
+                ("A;rm -rf ~", "x"),
+            ]),
+            ..Default::default()
+        };
+
+        let resolved = resolve(&ctx, &config, "feat", Path::new("/hub/feat"));
+        assert_eq!(
+            resolved.get("PROJECT"),
+            Some(&("app-feat".to_string(), Source::Config))
+        );
+        assert_eq!(
+            resolved.get("PORT"),
+            Some(&("3001".to_string(), Source::Hook))
+        );
+        assert_eq!(
+            resolved.get("DB"),
+            Some(&("mine".to_string(), Source::Store))
+        );
+        assert!(resolved.keys().all(|k| is_valid_key(k)));
+        assert_eq!(stored_names(&ctx), vec!["feat"]);
+
+        forget(&ctx, "feat").unwrap();
+        assert!(stored_names(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_merge_exports() {
+        let tmp = TempDir::new().unwrap();
+        let store = tmp.path().join("env/feat.hooks.env");
+        merge_exports(&store, "A=1\nB=2\n").unwrap();
+        merge_exports(&store, "B=3\n").unwrap();
+        assert_eq!(load_file(&store), vars(&[("A", "1"), ("B", "3")]));
+    }
+}